@@ -2,15 +2,19 @@ use futures::channel::mpsc::{channel, Sender, Receiver};
 use futures::StreamExt;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{AudioContext, AudioBuffer, AudioBufferSourceNode, GainNode};
+use web_sys::{AudioContext, AudioBuffer, AudioBufferSourceNode, AudioScheduledSourceNode, GainNode};
 use std::collections::HashMap;
 use std::sync::Mutex as StdMutex;
 use std::sync::Arc;
 
 pub enum AudioCommand {
     PlaySound(String),
+    // 以指定的播放速率播放音效，速率大于 1 时音调更高
+    PlaySoundAtRate(String, f32),
     PlayMusic(String),
     StopMusic,
+    PauseMusic,
+    ResumeMusic,
     SetVolume(f32),
 }
 
@@ -66,7 +70,7 @@ impl AudioSystem {
         let resp: web_sys::Response = resp_value.dyn_into()?;
         let array_buffer = wasm_bindgen_futures::JsFuture::from(resp.array_buffer()?).await?;
         
-        Ok(array_buffer.dyn_into()?)
+        array_buffer.dyn_into()
     }
     
     async fn decode_audio_data(&self, array_buffer: js_sys::ArrayBuffer) -> Result<AudioBuffer, JsValue> {
@@ -83,6 +87,11 @@ impl AudioSystem {
     pub fn play_sound(&self, name: &str) {
         let _ = self.command_sender.clone().try_send(AudioCommand::PlaySound(name.to_string()));
     }
+
+    // 同一个音效用不同音调播放，例如不同种类的宝藏
+    pub fn play_sound_at_rate(&self, name: &str, rate: f32) {
+        let _ = self.command_sender.clone().try_send(AudioCommand::PlaySoundAtRate(name.to_string(), rate));
+    }
     
     pub fn play_music(&self, name: &str) {
         let _ = self.command_sender.clone().try_send(AudioCommand::PlayMusic(name.to_string()));
@@ -92,6 +101,15 @@ impl AudioSystem {
         let _ = self.command_sender.clone().try_send(AudioCommand::StopMusic);
     }
     
+    // 暂停时挂起整个音频上下文，背景音乐会从暂停的位置继续播放
+    pub fn pause_music(&self) {
+        let _ = self.command_sender.clone().try_send(AudioCommand::PauseMusic);
+    }
+
+    pub fn resume_music(&self) {
+        let _ = self.command_sender.clone().try_send(AudioCommand::ResumeMusic);
+    }
+
     pub fn set_volume(&self, volume: f32) {
         let _ = self.command_sender.clone().try_send(AudioCommand::SetVolume(volume));
     }
@@ -115,11 +133,22 @@ impl AudioSystem {
                             }
                         }
                     }
+                    AudioCommand::PlaySoundAtRate(name, rate) => {
+                        if let Ok(buffers) = sound_buffers.lock() {
+                            if let Some(buffer) = buffers.get(&name) {
+                                let source = context.create_buffer_source().unwrap();
+                                source.set_buffer(Some(buffer));
+                                source.playback_rate().set_value(rate);
+                                source.connect_with_audio_node(&gain_node).unwrap();
+                                source.start().unwrap();
+                            }
+                        }
+                    }
                     AudioCommand::PlayMusic(name) => {
                         if let Ok(mut current) = current_music.lock() {
                             // 停止当前音乐
                             if let Some(source) = current.take() {
-                                let _ = source.stop();
+                                let _ = AsRef::<AudioScheduledSourceNode>::as_ref(&source).stop();
                             }
                             
                             // 播放新音乐
@@ -138,10 +167,16 @@ impl AudioSystem {
                     AudioCommand::StopMusic => {
                         if let Ok(mut current) = current_music.lock() {
                             if let Some(source) = current.take() {
-                                let _ = source.stop();
+                                let _ = AsRef::<AudioScheduledSourceNode>::as_ref(&source).stop();
                             }
                         }
                     }
+                    AudioCommand::PauseMusic => {
+                        let _ = context.suspend();
+                    }
+                    AudioCommand::ResumeMusic => {
+                        let _ = context.resume();
+                    }
                     AudioCommand::SetVolume(volume) => {
                         gain_node.gain().set_value(volume);
                     }
@@ -0,0 +1,157 @@
+// src/camera.rs
+// 摄像机：决定地图的哪一部分显示在画布（视口）上，地图可以比画布大。
+// 目标离开视口中央的死区时摄像机才移动，移动带平滑，并且不会移出地图边界
+pub const MIN_ZOOM: f64 = 0.25;
+pub const MAX_ZOOM: f64 = 4.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    // 视口左上角的世界坐标（像素）
+    x: f64,
+    y: f64,
+    // 视口大小（屏幕像素）
+    viewport_width: f64,
+    viewport_height: f64,
+    // 缩放倍数，大于 1 时放大
+    zoom: f64,
+    // 死区占视口的比例（0 到 1），目标在死区内移动时摄像机不动
+    deadzone: f64,
+    // 平滑系数（每秒），越大跟得越紧，为 0 时直接跳到目标位置
+    smoothing: f64,
+}
+
+impl Camera {
+    pub fn new(viewport_width: f64, viewport_height: f64) -> Camera {
+        Camera {
+            x: 0.0,
+            y: 0.0,
+            viewport_width,
+            viewport_height,
+            zoom: 1.0,
+            deadzone: 0.3,
+            smoothing: 8.0,
+        }
+    }
+
+    pub fn set_viewport(&mut self, width: f64, height: f64) {
+        self.viewport_width = width;
+        self.viewport_height = height;
+    }
+
+    pub fn set_zoom(&mut self, zoom: f64) {
+        if zoom.is_finite() {
+            self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+    }
+
+    pub fn set_deadzone(&mut self, deadzone: f64) {
+        if deadzone.is_finite() {
+            self.deadzone = deadzone.clamp(0.0, 1.0);
+        }
+    }
+
+    pub fn set_smoothing(&mut self, smoothing: f64) {
+        if smoothing.is_finite() {
+            self.smoothing = smoothing.max(0.0);
+        }
+    }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub fn viewport_width(&self) -> f64 {
+        self.viewport_width
+    }
+
+    pub fn viewport_height(&self) -> f64 {
+        self.viewport_height
+    }
+
+    // 视口能看到的世界范围（像素）
+    pub fn view_width(&self) -> f64 {
+        self.viewport_width / self.zoom
+    }
+
+    pub fn view_height(&self) -> f64 {
+        self.viewport_height / self.zoom
+    }
+
+    // 立即把目标（世界坐标）放在视口中央，用于开局、读档等不需要平滑的场合
+    pub fn snap_to(&mut self, target: (f64, f64), world: (f64, f64)) {
+        self.x = target.0 - self.view_width() / 2.0;
+        self.y = target.1 - self.view_height() / 2.0;
+        self.clamp_to(world);
+    }
+
+    // 跟随目标，world 是地图的大小（像素），delta_time 是经过的秒数
+    pub fn follow(&mut self, target: (f64, f64), world: (f64, f64), delta_time: f64) {
+        let desired_x = follow_axis(self.x, self.view_width(), self.deadzone, target.0);
+        let desired_y = follow_axis(self.y, self.view_height(), self.deadzone, target.1);
+
+        // 指数平滑，结果与帧率无关
+        let blend = if self.smoothing > 0.0 {
+            1.0 - (-self.smoothing * delta_time.max(0.0)).exp()
+        } else {
+            1.0
+        };
+        self.x += (desired_x - self.x) * blend;
+        self.y += (desired_y - self.y) * blend;
+        self.clamp_to(world);
+    }
+
+    // 不显示地图以外的区域，地图比视口小时居中显示
+    fn clamp_to(&mut self, world: (f64, f64)) {
+        self.x = clamp_axis(self.x, self.view_width(), world.0);
+        self.y = clamp_axis(self.y, self.view_height(), world.1);
+    }
+
+    pub fn world_to_screen(&self, x: f64, y: f64) -> (f64, f64) {
+        ((x - self.x) * self.zoom, (y - self.y) * self.zoom)
+    }
+
+    pub fn screen_to_world(&self, x: f64, y: f64) -> (f64, f64) {
+        (x / self.zoom + self.x, y / self.zoom + self.y)
+    }
+
+    // 视口内（至少部分可见）的格子范围 [x0, x1) x [y0, y1)，用于只绘制看得到的格子
+    pub fn visible_tiles(&self, tile_size: f64, map_width: usize, map_height: usize) -> (usize, usize, usize, usize) {
+        let range = |start: f64, length: f64, count: usize| {
+            let first = (start / tile_size).floor().max(0.0) as usize;
+            let last = ((start + length) / tile_size).ceil().max(0.0) as usize;
+            (first.min(count), last.min(count))
+        };
+        let (x0, x1) = range(self.x, self.view_width(), map_width);
+        let (y0, y1) = range(self.y, self.view_height(), map_height);
+        (x0, x1, y0, y1)
+    }
+}
+
+// 目标超出死区时，让死区边缘刚好贴着目标
+fn follow_axis(position: f64, view: f64, deadzone: f64, target: f64) -> f64 {
+    let center = position + view / 2.0;
+    let half = view * deadzone / 2.0;
+    if target < center - half {
+        target + half - view / 2.0
+    } else if target > center + half {
+        target - half - view / 2.0
+    } else {
+        position
+    }
+}
+
+fn clamp_axis(position: f64, view: f64, world: f64) -> f64 {
+    if world <= view {
+        (world - view) / 2.0
+    } else {
+        position.clamp(0.0, world - view)
+    }
+}
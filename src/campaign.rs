@@ -0,0 +1,168 @@
+// src/campaign.rs
+// 由多个关卡组成的战役：按顺序游玩，过关后进入下一关，分数在关卡之间累积
+use serde::{Deserialize, Serialize};
+
+use crate::cave::CaveSettings;
+use crate::config::{MAX_MAP_TILES, MAX_TREASURES, MIN_MAP_TILES};
+use crate::dungeon::DungeonSettings;
+use crate::level::Level;
+use crate::map::MapGenerator;
+use crate::maze::MazeAlgorithm;
+use crate::save;
+
+// 关卡地图的来源
+#[derive(Clone, Serialize, Deserialize)]
+pub enum LevelSource {
+    // 随机生成的地图，大小以格子为单位
+    Generated { generator: MapGenerator, width: usize, height: usize },
+    // 手工编辑的地图，宝藏位置由关卡决定
+    Authored(Level),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LevelDefinition {
+    pub name: String,
+    pub source: LevelSource,
+    // 随机生成的地图放置的宝藏数量，手工关卡忽略此项
+    pub treasure_count: u32,
+    // 时间限制（秒）
+    pub time_limit: f64,
+}
+
+impl LevelDefinition {
+    pub fn generated(name: &str, generator: MapGenerator, width: usize, height: usize, treasure_count: u32, time_limit: f64) -> LevelDefinition {
+        LevelDefinition {
+            name: name.to_string(),
+            source: LevelSource::Generated { generator, width, height },
+            treasure_count,
+            time_limit,
+        }
+    }
+
+    // 与 GameConfig 使用相同的限制：地图 3 到 256 格，宝藏 1 到 200 个，时间限制为正数
+    fn is_valid(&self) -> bool {
+        let source_valid = match &self.source {
+            LevelSource::Generated { width, height, .. } => {
+                let sizes = MIN_MAP_TILES..=MAX_MAP_TILES;
+                sizes.contains(width) && sizes.contains(height) && (1..=MAX_TREASURES).contains(&self.treasure_count)
+            }
+            LevelSource::Authored(level) => save::validate_level(level).is_ok(),
+        };
+        source_valid && self.time_limit.is_finite() && self.time_limit > 0.0
+    }
+
+    pub fn authored(name: &str, level: Level, time_limit: f64) -> LevelDefinition {
+        LevelDefinition {
+            name: name.to_string(),
+            treasure_count: level.treasures.len() as u32,
+            source: LevelSource::Authored(level),
+            time_limit,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Campaign {
+    levels: Vec<LevelDefinition>,
+    current: usize,
+}
+
+impl Campaign {
+    pub fn new(levels: Vec<LevelDefinition>) -> Campaign {
+        assert!(!levels.is_empty(), "campaign needs at least one level");
+        Campaign { levels, current: 0 }
+    }
+
+    // 内置战役，地图大小以格子为单位，难度逐关上升
+    pub fn standard(width: usize, height: usize) -> Campaign {
+        Campaign::new(vec![
+            LevelDefinition::generated("Open Field", MapGenerator::Obstacles, width, height, 5, 60.0),
+            LevelDefinition::generated("Caverns", MapGenerator::Cave(CaveSettings::default()), width, height, 6, 60.0),
+            LevelDefinition::generated("Dungeon", MapGenerator::Dungeon(DungeonSettings::default()), width, height, 7, 75.0),
+            LevelDefinition::generated(
+                "Labyrinth",
+                MapGenerator::BraidedMaze { algorithm: MazeAlgorithm::RecursiveBacktracker, loop_ratio: 0.5 },
+                width,
+                height,
+                8,
+                90.0,
+            ),
+        ])
+    }
+
+    pub fn levels(&self) -> &[LevelDefinition] {
+        &self.levels
+    }
+
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    // 当前关卡的序号，从 0 开始
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    pub fn current(&self) -> &LevelDefinition {
+        &self.levels[self.current]
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.current + 1 < self.levels.len()
+    }
+
+    // 进入下一关，已经是最后一关时返回 false
+    pub fn advance(&mut self) -> bool {
+        if self.has_next() {
+            self.current += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // 回到第一关
+    pub fn restart(&mut self) {
+        self.current = 0;
+    }
+
+    // 存档中的战役可能来自被修改过的文件
+    pub fn is_consistent(&self) -> bool {
+        self.current < self.levels.len() && self.levels.iter().all(LevelDefinition::is_valid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single(definition: LevelDefinition) -> Campaign {
+        Campaign::new(vec![definition])
+    }
+
+    #[test]
+    fn standard_campaign_is_consistent() {
+        assert!(Campaign::standard(20, 15).is_consistent());
+    }
+
+    #[test]
+    fn rejects_generated_levels_outside_the_config_bounds() {
+        let level = |width, height, treasures, time| {
+            single(LevelDefinition::generated("x", MapGenerator::Obstacles, width, height, treasures, time))
+        };
+        assert!(level(20, 15, 5, 60.0).is_consistent());
+        assert!(!level(100_000, 100_000, 5, 60.0).is_consistent());
+        assert!(!level(2, 15, 5, 60.0).is_consistent());
+        assert!(!level(20, 15, 0, 60.0).is_consistent());
+        assert!(!level(20, 15, 50_000_000, 60.0).is_consistent());
+        assert!(!level(20, 15, 5, -5.0).is_consistent());
+        assert!(!level(20, 15, 5, f64::INFINITY).is_consistent());
+    }
+
+    #[test]
+    fn rejects_a_current_level_out_of_range() {
+        let mut campaign = Campaign::standard(20, 15);
+        campaign.current = campaign.level_count();
+        assert!(!campaign.is_consistent());
+    }
+}
@@ -0,0 +1,122 @@
+// src/cave.rs
+// 元胞自动机洞穴生成：随机填充，若干轮平滑，最后只保留最大的连通区域
+use serde::{Deserialize, Serialize};
+
+use crate::map::{GameMap, TileType};
+use crate::rng::Rng;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CaveSettings {
+    // 初始随机填充为墙的比例（0 到 1）
+    pub fill_ratio: f64,
+    // 平滑迭代次数
+    pub iterations: u32,
+    // 空地周围的墙数达到该值时变成墙
+    pub birth_limit: u8,
+    // 墙周围的墙数达到该值时保持为墙
+    pub survival_limit: u8,
+}
+
+impl Default for CaveSettings {
+    fn default() -> Self {
+        CaveSettings {
+            fill_ratio: 0.45,
+            iterations: 5,
+            birth_limit: 5,
+            survival_limit: 4,
+        }
+    }
+}
+
+pub fn generate(map: &mut GameMap, settings: CaveSettings, rng: &mut Rng) {
+    let (width, height) = (map.width(), map.height());
+
+    // 随机填充，外圈始终是墙
+    let mut walls = vec![true; width * height];
+    for y in 1..height.saturating_sub(1) {
+        for x in 1..width.saturating_sub(1) {
+            walls[y * width + x] = rng.chance(settings.fill_ratio);
+        }
+    }
+
+    for _ in 0..settings.iterations {
+        walls = smooth(&walls, width, height, settings);
+    }
+
+    for y in 0..height {
+        for x in 0..width {
+            let tile = if walls[y * width + x] { TileType::Wall } else { TileType::Empty };
+            map.set_tile(x, y, tile);
+        }
+    }
+
+    keep_largest_region(map);
+}
+
+fn smooth(walls: &[bool], width: usize, height: usize, settings: CaveSettings) -> Vec<bool> {
+    let mut next = vec![true; width * height];
+    for y in 1..height.saturating_sub(1) {
+        for x in 1..width.saturating_sub(1) {
+            let count = wall_neighbors(walls, width, x, y);
+            next[y * width + x] = if walls[y * width + x] {
+                count >= settings.survival_limit
+            } else {
+                count >= settings.birth_limit
+            };
+        }
+    }
+    next
+}
+
+// 周围八个格子中墙的数量
+fn wall_neighbors(walls: &[bool], width: usize, x: usize, y: usize) -> u8 {
+    let mut count = 0;
+    for ny in y - 1..=y + 1 {
+        for nx in x - 1..=x + 1 {
+            if (nx, ny) != (x, y) && walls[ny * width + nx] {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+// 把除最大连通区域以外的空地都填成墙
+fn keep_largest_region(map: &mut GameMap) {
+    let (width, height) = (map.width(), map.height());
+    let mut region_of = vec![usize::MAX; width * height];
+    let mut largest: Option<(usize, usize)> = None; // (区域编号, 大小)
+    let mut region_count = 0;
+
+    for y in 0..height {
+        for x in 0..width {
+            if !map.is_tile_walkable(x, y) || region_of[y * width + x] != usize::MAX {
+                continue;
+            }
+
+            let reachable = map.reachable_from(x, y);
+            let mut size = 0;
+            for (index, &is_reachable) in reachable.iter().enumerate() {
+                if is_reachable {
+                    region_of[index] = region_count;
+                    size += 1;
+                }
+            }
+            if largest.is_none_or(|(_, largest_size)| size > largest_size) {
+                largest = Some((region_count, size));
+            }
+            region_count += 1;
+        }
+    }
+
+    if let Some((keep, _)) = largest {
+        for y in 0..height {
+            for x in 0..width {
+                let region = region_of[y * width + x];
+                if region != usize::MAX && region != keep {
+                    map.set_tile(x, y, TileType::Wall);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+// src/combo.rs
+// 连击：上一次收集后 window 秒内再次收集时倍率增加，超时后倍率按每秒 decay 回落到 1，
+// 回落到 1 之前再次收集仍然算连击
+use serde::{Deserialize, Serialize};
+
+pub const COMBO_STEP: f64 = 0.5;
+pub const MAX_COMBO_MULTIPLIER: f64 = 4.0;
+
+// 存档只保存连击进度，窗口和回落速度由 GameConfig 决定
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Combo {
+    // 连击窗口（秒），为 0 时关闭连击
+    #[serde(skip)]
+    window: f64,
+    // 窗口结束后倍率每秒下降的量
+    #[serde(skip)]
+    decay: f64,
+    multiplier: f64,
+    // 当前连击中收集的宝藏数
+    chain: u32,
+    // 窗口剩余时间（秒）
+    timer: f64,
+}
+
+impl Combo {
+    pub fn new(window: f64, decay: f64) -> Combo {
+        Combo {
+            window,
+            decay,
+            multiplier: 1.0,
+            chain: 0,
+            timer: 0.0,
+        }
+    }
+
+    pub fn set_settings(&mut self, window: f64, decay: f64) {
+        self.window = window;
+        self.decay = decay;
+    }
+
+    // 记录一次收集，返回这次收集的倍率
+    pub fn hit(&mut self) -> f64 {
+        if self.chain > 0 && (self.timer > 0.0 || self.multiplier > 1.0) {
+            self.chain += 1;
+            self.multiplier = (self.multiplier + COMBO_STEP).min(MAX_COMBO_MULTIPLIER);
+        } else {
+            self.chain = 1;
+            self.multiplier = 1.0;
+        }
+        self.timer = self.window;
+        self.multiplier
+    }
+
+    // 恢复存档中的连击进度，保留当前的窗口和回落速度
+    pub fn restore(&mut self, saved: &Combo) {
+        self.multiplier = saved.multiplier;
+        self.chain = saved.chain;
+        self.timer = saved.timer;
+    }
+
+    // 连击中断（例如收集到被诅咒的宝藏）
+    pub fn reset(&mut self) {
+        self.multiplier = 1.0;
+        self.chain = 0;
+        self.timer = 0.0;
+    }
+
+    pub fn update(&mut self, delta_time: f64) {
+        if self.timer > 0.0 {
+            self.timer = (self.timer - delta_time).max(0.0);
+        } else if self.multiplier > 1.0 {
+            self.multiplier = (self.multiplier - self.decay * delta_time).max(1.0);
+        } else {
+            self.chain = 0;
+        }
+    }
+
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    pub fn chain(&self) -> u32 {
+        self.chain
+    }
+
+    // 窗口剩余的比例（0 到 1），用于显示倒计时条
+    pub fn remaining(&self) -> f64 {
+        if self.window > 0.0 {
+            (self.timer / self.window).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Combo::new(2.0, 1.0)
+    }
+}
@@ -0,0 +1,269 @@
+// src/config.rs
+// 游戏规则参数：地图和格子大小、宝藏数量和分值、时间限制、障碍物密度、守卫、战争迷雾和玩家移动手感
+// JSON 中缺少的字段使用默认值，拼错的字段名会报错
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::guard::GuardContact;
+use crate::player::{Movement, PLAYER_SIZE};
+
+// 地图至少要有 3x3 个格子（外墙加一格空地）
+pub const MIN_MAP_TILES: usize = 3;
+
+// 地图每个方向最多的格子数
+pub const MAX_MAP_TILES: usize = 256;
+
+// 每局最多的宝藏和守卫数量
+pub const MAX_TREASURES: u32 = 200;
+pub const MAX_GUARDS: u32 = 50;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    // 格子边长（像素）
+    pub tile_size: f64,
+    // 地图的宽和高（格子数），为 0 时按创建游戏时给出的大小铺满
+    pub map_width: u32,
+    pub map_height: u32,
+    // 每局随机放置的宝藏数量
+    pub treasure_count: u32,
+    // 每局的时间限制（秒）
+    pub time_limit: f64,
+    // 每个宝藏的分值
+    pub treasure_value: u32,
+    // 连击窗口（秒）：上一次收集后这段时间内再次收集时倍率增加，为 0 时关闭连击
+    pub combo_window: f64,
+    // 窗口结束后连击倍率每秒下降的量
+    pub combo_decay: f64,
+    // 宝藏之间的最小间距（格子数）
+    pub treasure_min_spread: f64,
+    // 障碍物地图中障碍物占地图的比例（0 到 1）
+    pub obstacle_density: f64,
+    // 每局放置的守卫数量
+    pub guard_count: u32,
+    // 守卫巡逻速度（像素/秒），追赶时更快
+    pub guard_speed: f64,
+    // 守卫的视野距离（格子数）
+    pub guard_view_range: f64,
+    // 被守卫抓到的后果
+    pub guard_contact: GuardContact,
+    // 是否只显示探索过的格子和视野内的宝藏、守卫
+    pub fog_of_war: bool,
+    // 玩家的视野半径（格子数）
+    pub view_radius: f64,
+    pub movement: Movement,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            tile_size: 40.0,
+            map_width: 0,
+            map_height: 0,
+            treasure_count: 5,
+            time_limit: 60.0,
+            treasure_value: 10,
+            combo_window: 2.0,
+            combo_decay: 1.0,
+            treasure_min_spread: 0.0,
+            obstacle_density: 0.1,
+            guard_count: 2,
+            guard_speed: 100.0,
+            guard_view_range: 5.0,
+            guard_contact: GuardContact::default(),
+            fog_of_war: true,
+            view_radius: 5.0,
+            movement: Movement::default(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ConfigError {
+    Json(String),
+    InvalidValue { field: &'static str, reason: &'static str },
+    MapTooSmall { width: usize, height: usize },
+    MapTooLarge { width: usize, height: usize },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Json(message) => write!(f, "invalid game config: {}", message),
+            ConfigError::InvalidValue { field, reason } => write!(f, "invalid {}: {}", field, reason),
+            ConfigError::MapTooSmall { width, height } => write!(
+                f,
+                "map would be {}x{} tiles, at least {}x{} are needed",
+                width, height, MIN_MAP_TILES, MIN_MAP_TILES
+            ),
+            ConfigError::MapTooLarge { width, height } => write!(
+                f,
+                "map would be {}x{} tiles, at most {}x{} are allowed",
+                width, height, MAX_MAP_TILES, MAX_MAP_TILES
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<serde_json::Error> for ConfigError {
+    fn from(error: serde_json::Error) -> Self {
+        ConfigError::Json(error.to_string())
+    }
+}
+
+impl GameConfig {
+    pub fn from_json(text: &str) -> Result<GameConfig, ConfigError> {
+        let config: GameConfig = serde_json::from_str(text)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("game config is always serializable")
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        // 格子比玩家小时一格宽的通道走不过去
+        if !self.tile_size.is_finite() || self.tile_size < PLAYER_SIZE {
+            return Err(ConfigError::InvalidValue { field: "tile_size", reason: "must be at least the player size (20)" });
+        }
+        if self.map_width as usize > MAX_MAP_TILES {
+            return Err(ConfigError::InvalidValue { field: "map_width", reason: "must be at most 256 tiles" });
+        }
+        if self.map_height as usize > MAX_MAP_TILES {
+            return Err(ConfigError::InvalidValue { field: "map_height", reason: "must be at most 256 tiles" });
+        }
+        if !(1..=MAX_TREASURES).contains(&self.treasure_count) {
+            return Err(ConfigError::InvalidValue { field: "treasure_count", reason: "must be between 1 and 200" });
+        }
+        if self.guard_count > MAX_GUARDS {
+            return Err(ConfigError::InvalidValue { field: "guard_count", reason: "must be at most 50" });
+        }
+        check_positive("time_limit", self.time_limit)?;
+        if !self.combo_window.is_finite() || self.combo_window < 0.0 {
+            return Err(ConfigError::InvalidValue {
+                field: "combo_window",
+                reason: "must be zero or a positive number",
+            });
+        }
+        check_positive("combo_decay", self.combo_decay)?;
+        if !self.treasure_min_spread.is_finite() || self.treasure_min_spread < 0.0 {
+            return Err(ConfigError::InvalidValue {
+                field: "treasure_min_spread",
+                reason: "must be zero or a positive number",
+            });
+        }
+        if !(0.0..1.0).contains(&self.obstacle_density) {
+            return Err(ConfigError::InvalidValue {
+                field: "obstacle_density",
+                reason: "must be at least 0 and less than 1",
+            });
+        }
+        check_positive("guard_speed", self.guard_speed)?;
+        check_positive("guard_view_range", self.guard_view_range)?;
+        if let GuardContact::Penalty(seconds) = self.guard_contact {
+            check_positive("guard_contact", seconds)?;
+        }
+        check_positive("view_radius", self.view_radius)?;
+        check_positive("movement.acceleration", self.movement.acceleration)?;
+        check_positive("movement.max_speed", self.movement.max_speed)?;
+        check_positive("movement.friction", self.movement.friction)?;
+        Ok(())
+    }
+
+    // 给出的大小为 width x height 像素时地图的格子数，指定了 map_width / map_height 时使用指定的值
+    pub fn map_size(&self, width: u32, height: u32) -> (usize, usize) {
+        let tiles = |size: u32, tiles: u32| {
+            if tiles > 0 {
+                tiles as usize
+            } else {
+                (size as f64 / self.tile_size) as usize
+            }
+        };
+        (tiles(width, self.map_width), tiles(height, self.map_height))
+    }
+
+    // 地图的像素大小，没有指定格子数的方向沿用给出的大小
+    pub fn world_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let (map_width, map_height) = self.map_size(width, height);
+        let size = |size: u32, tiles: u32, count: usize| {
+            if tiles > 0 {
+                (count as f64 * self.tile_size) as u32
+            } else {
+                size
+            }
+        };
+        (size(width, self.map_width, map_width), size(height, self.map_height, map_height))
+    }
+
+    // 检查参数本身以及按这个格子大小铺满画布后地图的格子数是否合适
+    pub fn validate_for(&self, width: u32, height: u32) -> Result<(), ConfigError> {
+        self.validate()?;
+        let (map_width, map_height) = self.map_size(width, height);
+        if map_width < MIN_MAP_TILES || map_height < MIN_MAP_TILES {
+            return Err(ConfigError::MapTooSmall { width: map_width, height: map_height });
+        }
+        if map_width > MAX_MAP_TILES || map_height > MAX_MAP_TILES {
+            return Err(ConfigError::MapTooLarge { width: map_width, height: map_height });
+        }
+        Ok(())
+    }
+}
+
+fn check_positive(field: &'static str, value: f64) -> Result<(), ConfigError> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidValue { field, reason: "must be a positive number" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid_field(json: &str) -> Option<&'static str> {
+        match GameConfig::from_json(json) {
+            Err(ConfigError::InvalidValue { field, .. }) => Some(field),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        assert_eq!(GameConfig::default().validate_for(800, 600), Ok(()));
+        assert_eq!(GameConfig::from_json("{}"), Ok(GameConfig::default()));
+    }
+
+    #[test]
+    fn rejects_unbounded_counts() {
+        assert_eq!(invalid_field(r#"{"treasure_count":50000000}"#), Some("treasure_count"));
+        assert_eq!(invalid_field(r#"{"treasure_count":0}"#), Some("treasure_count"));
+        assert_eq!(invalid_field(r#"{"guard_count":4000000000}"#), Some("guard_count"));
+        assert!(GameConfig::from_json(r#"{"treasure_count":200,"guard_count":50}"#).is_ok());
+    }
+
+    #[test]
+    fn rejects_tiles_smaller_than_the_player() {
+        assert_eq!(invalid_field(r#"{"tile_size":0.05}"#), Some("tile_size"));
+        assert_eq!(invalid_field(r#"{"tile_size":10}"#), Some("tile_size"));
+        assert!(GameConfig::from_json(r#"{"tile_size":20}"#).is_ok());
+    }
+
+    #[test]
+    fn limits_the_map_size_derived_from_the_canvas() {
+        let config = GameConfig { tile_size: 20.0, ..GameConfig::default() };
+        assert_eq!(config.validate_for(5120, 600), Ok(()));
+        assert_eq!(
+            config.validate_for(5140, 600),
+            Err(ConfigError::MapTooLarge { width: 257, height: 30 })
+        );
+        assert_eq!(
+            config.validate_for(40, 600),
+            Err(ConfigError::MapTooSmall { width: 2, height: 30 })
+        );
+    }
+}
@@ -0,0 +1,92 @@
+// src/difficulty.rs
+// 难度预设：在 GameConfig（或战役关卡）的基础上调整障碍物密度、宝藏数量和间距、守卫数量以及时间限制
+use serde::{Deserialize, Serialize};
+
+// 难度调整后障碍物密度的上限，保证地图上还有足够的空地
+const MAX_OBSTACLE_DENSITY: f64 = 0.35;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Expert];
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            "expert" => Some(Difficulty::Expert),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+
+    // 障碍物密度
+    pub fn obstacle_density(self, base: f64) -> f64 {
+        let factor = match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+            Difficulty::Expert => 2.0,
+        };
+        (base * factor).min(MAX_OBSTACLE_DENSITY.max(base))
+    }
+
+    // 宝藏数量，至少一个
+    pub fn treasure_count(self, base: u32) -> u32 {
+        let factor = match self {
+            Difficulty::Easy => 0.8,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.4,
+            Difficulty::Expert => 1.8,
+        };
+        ((base as f64 * factor).round() as u32).max(1)
+    }
+
+    // 宝藏之间的最小间距（格子数），难度越高宝藏越分散
+    pub fn treasure_min_spread(self, base: f64) -> f64 {
+        let spread: f64 = match self {
+            Difficulty::Easy | Difficulty::Normal => 0.0,
+            Difficulty::Hard => 3.0,
+            Difficulty::Expert => 5.0,
+        };
+        spread.max(base)
+    }
+
+    // 守卫数量，为 0 时保持没有守卫
+    pub fn guard_count(self, base: u32) -> u32 {
+        let factor = match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+            Difficulty::Expert => 2.0,
+        };
+        (base as f64 * factor).round() as u32
+    }
+
+    // 时间限制（秒）
+    pub fn time_limit(self, base: f64) -> f64 {
+        let factor = match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.8,
+            Difficulty::Expert => 0.6,
+        };
+        base * factor
+    }
+}
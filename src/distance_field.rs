@@ -0,0 +1,207 @@
+// src/distance_field.rs
+// 距离场：用 Dijkstra 算出每个格子到最近的目标（例如还没收集的宝藏）要走多远，
+// 沿着距离下降的方向走就能到达目标，任意多个角色都可以共用同一个距离场
+use std::collections::BinaryHeap;
+
+use crate::map::GameMap;
+use crate::pathfinding::{self, Connectivity, Node};
+
+pub struct DistanceField {
+    width: usize,
+    // 每个格子到最近目标的距离（格子数），走不到时为无穷大
+    distances: Vec<f64>,
+    // 每个格子最近的目标编号
+    nearest: Vec<Option<usize>>,
+    // 每个目标所在的格子序号，移除后为 None
+    sources: Vec<Option<usize>>,
+}
+
+impl DistanceField {
+    // sources[i] 是编号为 i 的目标所在的格子，None 表示没有这个目标
+    pub fn new(map: &GameMap, sources: &[Option<(usize, usize)>]) -> DistanceField {
+        let width = map.width();
+        let size = width * map.height();
+        let mut field = DistanceField {
+            width,
+            distances: vec![f64::INFINITY; size],
+            nearest: vec![None; size],
+            sources: sources.iter()
+                .map(|source| source.filter(|&(x, y)| map.is_tile_walkable(x, y)).map(|(x, y)| y * width + x))
+                .collect(),
+        };
+
+        let mut open = BinaryHeap::new();
+        for (id, tile) in field.sources.iter().enumerate() {
+            if let Some(index) = *tile {
+                if field.nearest[index].is_none() {
+                    field.distances[index] = 0.0;
+                    field.nearest[index] = Some(id);
+                    open.push(Node { f: 0.0, index });
+                }
+            }
+        }
+        field.expand(map, open);
+        field
+    }
+
+    // 移除一个目标（例如宝藏被收集），只重新计算原来离它最近的那些格子
+    pub fn remove_source(&mut self, map: &GameMap, id: usize) {
+        if self.sources.get(id).copied().flatten().is_none() {
+            return;
+        }
+        self.sources[id] = None;
+
+        let affected: Vec<usize> = (0..self.distances.len())
+            .filter(|&index| self.nearest[index] == Some(id))
+            .collect();
+        for &index in &affected {
+            self.distances[index] = f64::INFINITY;
+            self.nearest[index] = None;
+        }
+
+        let mut open = BinaryHeap::new();
+        // 和被移除的目标在同一个格子里的其他目标
+        for (other, tile) in self.sources.iter().enumerate() {
+            if let Some(index) = *tile {
+                if self.nearest[index].is_none() {
+                    self.distances[index] = 0.0;
+                    self.nearest[index] = Some(other);
+                    open.push(Node { f: 0.0, index });
+                }
+            }
+        }
+        // 受影响区域外的格子距离仍然正确，从边界向内重新扩展
+        for &index in &affected {
+            for (neighbor, cost) in pathfinding::neighbors(map, self.tile(index), Connectivity::Eight) {
+                let from = neighbor.1 * self.width + neighbor.0;
+                let distance = self.distances[from] + cost;
+                if distance < self.distances[index] {
+                    self.distances[index] = distance;
+                    self.nearest[index] = self.nearest[from];
+                }
+            }
+            if self.distances[index].is_finite() {
+                open.push(Node { f: self.distances[index], index });
+            }
+        }
+        self.expand(map, open);
+    }
+
+    // Dijkstra：从队列中的格子向外扩展，更新更近的格子
+    fn expand(&mut self, map: &GameMap, mut open: BinaryHeap<Node>) {
+        while let Some(Node { f, index }) = open.pop() {
+            if f > self.distances[index] {
+                continue;
+            }
+            for (neighbor, cost) in pathfinding::neighbors(map, self.tile(index), Connectivity::Eight) {
+                let next = neighbor.1 * self.width + neighbor.0;
+                if f + cost < self.distances[next] {
+                    self.distances[next] = f + cost;
+                    self.nearest[next] = self.nearest[index];
+                    open.push(Node { f: f + cost, index: next });
+                }
+            }
+        }
+    }
+
+    fn tile(&self, index: usize) -> (usize, usize) {
+        (index % self.width, index / self.width)
+    }
+
+    // 格子到最近目标的距离（格子数），走不到或没有目标时返回 None
+    pub fn distance(&self, x: usize, y: usize) -> Option<f64> {
+        if x >= self.width {
+            return None;
+        }
+        self.distances.get(y * self.width + x).copied().filter(|distance| distance.is_finite())
+    }
+
+    // 还剩几个目标
+    pub fn source_count(&self) -> usize {
+        self.sources.iter().flatten().count()
+    }
+
+    // 从这个格子出发下一步应该走到的相邻格子，已经在目标上或走不到时返回 None
+    pub fn downhill(&self, map: &GameMap, x: usize, y: usize) -> Option<(usize, usize)> {
+        let current = self.distance(x, y)?;
+        pathfinding::neighbors(map, (x, y), Connectivity::Eight)
+            .into_iter()
+            .map(|(tile, _)| (tile, self.distances[tile.1 * self.width + tile.0]))
+            .filter(|&(_, distance)| distance < current)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(tile, _)| tile)
+    }
+
+    // 从像素坐标 (x, y) 朝最近目标前进的方向（单位向量）
+    pub fn direction(&self, map: &GameMap, x: f64, y: f64) -> Option<(f64, f64)> {
+        let (tile_x, tile_y) = map.tile_at(x, y);
+        let (next_x, next_y) = self.downhill(map, tile_x, tile_y)?;
+        let tile_size = map.tile_size();
+        let dx = (next_x as f64 + 0.5) * tile_size - x;
+        let dy = (next_y as f64 + 0.5) * tile_size - y;
+        let length = dx.hypot(dy);
+        (length > 0.0).then(|| (dx / length, dy / length))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+
+    const LEVEL: &str = "\
+##########
+#P..o...T#
+#.oo#.o..#
+#T..#..oT#
+#..o..o..#
+#T.....o.#
+##########
+";
+
+    fn assert_same_distances(map: &GameMap, field: &DistanceField, expected: &DistanceField) {
+        for y in 0..map.height() {
+            for x in 0..map.width() {
+                match (field.distance(x, y), expected.distance(x, y)) {
+                    (Some(a), Some(b)) => assert!((a - b).abs() < 1e-9, "({}, {}): {} != {}", x, y, a, b),
+                    (a, b) => assert_eq!(a, b, "({}, {})", x, y),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn removing_sources_matches_a_full_rebuild() {
+        let level = Level::parse(LEVEL, 40.0).unwrap();
+        let map = &level.map;
+        let mut sources: Vec<Option<(usize, usize)>> = level.treasures.iter().copied().map(Some).collect();
+        // 两个目标在同一个格子里
+        sources.push(Some(level.treasures[1]));
+
+        let mut field = DistanceField::new(map, &sources);
+        for id in [1, 3, 0, sources.len() - 1, 2, 4] {
+            field.remove_source(map, id);
+            sources[id] = None;
+            assert_eq!(field.source_count(), sources.iter().flatten().count());
+            assert_same_distances(map, &field, &DistanceField::new(map, &sources));
+        }
+
+        // 最后一个目标被移除后哪里都走不到
+        assert_eq!(field.source_count(), 0);
+        assert_eq!(field.distance(1, 1), None);
+        assert_eq!(field.downhill(map, 1, 1), None);
+    }
+
+    #[test]
+    fn removing_a_missing_source_does_nothing() {
+        let level = Level::parse(LEVEL, 40.0).unwrap();
+        let sources: Vec<_> = level.treasures.iter().copied().map(Some).collect();
+        let mut field = DistanceField::new(&level.map, &sources);
+        field.remove_source(&level.map, 0);
+        field.remove_source(&level.map, 0);
+        field.remove_source(&level.map, sources.len());
+        let mut expected = sources.clone();
+        expected[0] = None;
+        assert_same_distances(&level.map, &field, &DistanceField::new(&level.map, &expected));
+    }
+}
@@ -0,0 +1,123 @@
+// src/dungeon.rs
+// 房间加走廊的地牢生成：随机放置互不重叠的房间，再用 L 形走廊依次连接
+use serde::{Deserialize, Serialize};
+
+use crate::map::{GameMap, Room, TileType};
+use crate::rng::Rng;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DungeonSettings {
+    // 最多放置的房间数
+    pub max_rooms: u32,
+    // 房间边长范围（格子数，包含两端）
+    pub min_room_size: usize,
+    pub max_room_size: usize,
+    // 除入口房间外被标记为宝藏房间的比例（0 到 1）
+    pub treasure_room_ratio: f64,
+}
+
+impl Default for DungeonSettings {
+    fn default() -> Self {
+        DungeonSettings {
+            max_rooms: 8,
+            min_room_size: 3,
+            max_room_size: 6,
+            treasure_room_ratio: 0.3,
+        }
+    }
+}
+
+pub fn generate(map: &mut GameMap, settings: DungeonSettings, rng: &mut Rng) {
+    let (width, height) = (map.width(), map.height());
+    for y in 0..height {
+        for x in 0..width {
+            map.set_tile(x, y, TileType::Wall);
+        }
+    }
+    if width < 3 || height < 3 {
+        return;
+    }
+
+    let max_size = settings.max_room_size.max(settings.min_room_size).max(1);
+    let min_size = settings.min_room_size.clamp(1, max_size);
+    let mut rooms: Vec<Room> = Vec::new();
+
+    // 入口房间固定在左上角，保证出生点在房间里
+    let entrance = Room::new(
+        1,
+        1,
+        rng.range(min_size, max_size + 1).min(width - 2),
+        rng.range(min_size, max_size + 1).min(height - 2),
+    );
+    rooms.push(entrance);
+
+    let max_attempts = settings.max_rooms * 20;
+    let mut attempts = 0;
+    while (rooms.len() as u32) < settings.max_rooms && attempts < max_attempts {
+        attempts += 1;
+        let room_width = rng.range(min_size, max_size + 1);
+        let room_height = rng.range(min_size, max_size + 1);
+        if room_width + 2 > width || room_height + 2 > height {
+            continue;
+        }
+
+        let room = Room::new(
+            rng.range(1, width - room_width),
+            rng.range(1, height - room_height),
+            room_width,
+            room_height,
+        );
+        // 房间之间至少隔一格墙
+        if rooms.iter().any(|other| room.intersects(other, 1)) {
+            continue;
+        }
+        rooms.push(room);
+    }
+
+    for room in &rooms {
+        for y in room.y..room.y + room.height {
+            for x in room.x..room.x + room.width {
+                map.set_tile(x, y, TileType::Empty);
+            }
+        }
+    }
+
+    // 每个房间连到上一个房间，保证整个地牢连通
+    for pair in rooms.windows(2) {
+        let (ax, ay) = pair[0].center();
+        let (bx, by) = pair[1].center();
+        if rng.chance(0.5) {
+            carve_horizontal(map, ax, bx, ay);
+            carve_vertical(map, ay, by, bx);
+        } else {
+            carve_vertical(map, ay, by, ax);
+            carve_horizontal(map, ax, bx, by);
+        }
+    }
+
+    // 入口房间之外按比例标记宝藏房间，至少一个
+    let candidates = rooms.len().saturating_sub(1);
+    if candidates > 0 {
+        let treasure_rooms = ((candidates as f64 * settings.treasure_room_ratio).round() as usize)
+            .clamp(1, candidates);
+        let mut indices: Vec<usize> = (1..rooms.len()).collect();
+        rng.shuffle(&mut indices);
+        for &index in indices.iter().take(treasure_rooms) {
+            rooms[index].is_treasure_room = true;
+        }
+    }
+
+    map.set_rooms(rooms);
+}
+
+fn carve_horizontal(map: &mut GameMap, x1: usize, x2: usize, y: usize) {
+    for x in x1.min(x2)..=x1.max(x2) {
+        map.set_tile(x, y, TileType::Empty);
+    }
+}
+
+fn carve_vertical(map: &mut GameMap, y1: usize, y2: usize, x: usize) {
+    for y in y1.min(y2)..=y1.max(y2) {
+        map.set_tile(x, y, TileType::Empty);
+    }
+}
@@ -1,112 +1,438 @@
 // src/game.rs
+// 浏览器端的 wasm 绑定层：负责时间戳、键盘输入、音频和日志，游戏规则全部交给 Simulation
 use wasm_bindgen::prelude::*;
 use web_sys::console;
-use crate::player::Player;
-use crate::map::{GameMap, TileType};
-use crate::treasure::TreasureManager;
+use crate::camera::Camera;
+use crate::campaign::Campaign;
+use crate::cave::CaveSettings;
+use crate::config;
+use crate::difficulty::Difficulty;
+use crate::dungeon::DungeonSettings;
+use crate::guard::GuardContact;
+use crate::map::{MapGenerator, TileType};
+use crate::maze::MazeAlgorithm;
+use crate::pathfinding::{self, Connectivity};
 use crate::audio::AudioSystem;
+use crate::leaderboard::{Leaderboard, LeaderboardEntry, MemoryStorage, Storage, LEADERBOARD_SIZE};
+use crate::level::Level;
+use crate::local_storage::LocalStorage;
+use crate::player::Movement;
+use crate::save::{LevelFile, SaveState};
+use crate::tiled;
+use crate::treasure::TreasureKind;
+use crate::simulation::{self, GameEvent, Input, Simulation};
+
+// 排行榜在 localStorage 中的键
+const LEADERBOARD_KEY: &str = "treasure-hunt-leaderboard";
+
+// 玩家名字的最大长度（字符数）
+const MAX_PLAYER_NAME_LENGTH: usize = 20;
 
 #[wasm_bindgen]
 pub struct Game {
-    width: u32,
-    height: u32,
-    is_running: bool,
+    simulation: Simulation,
     last_frame_time: f64,
-    player: Player,
-    map: GameMap,
     keys_pressed: Vec<String>,
-    treasure_manager: TreasureManager,
-    state: GameState,
-    game_time: f64, 
-    time_limit: f64,   
-    best_time: Option<f64>,  
     first_timestamp: Option<f64>,
     audio: Option<AudioSystem>,
+    storage: Box<dyn Storage>,
+    leaderboard: Leaderboard,
+    player_name: String,
+    // 最近一次成绩在排行榜上的名次（从 0 开始）
+    last_rank: Option<usize>,
+    camera: Camera,
 }
 
 #[wasm_bindgen]
 #[derive(Clone, Copy, PartialEq)]
 pub enum GameState {
+    Menu,
+    LevelIntro,
     Playing,
-    Won,
-    TimeUp,
+    Paused,
+    Results,
+    GameOver,
+}
+
+// JS 端的游戏规则，未设置的字段使用默认值，传给 Game 时才检查是否合理
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct GameConfig {
+    config: config::GameConfig,
+}
+
+#[wasm_bindgen]
+impl GameConfig {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> GameConfig {
+        GameConfig { config: config::GameConfig::default() }
+    }
+
+    // 从 JSON 创建，缺少的字段使用默认值，未知字段或不合理的值返回错误
+    #[wasm_bindgen]
+    pub fn from_json(json: &str) -> Result<GameConfig, JsValue> {
+        let config = config::GameConfig::from_json(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(GameConfig { config })
+    }
+
+    #[wasm_bindgen]
+    pub fn to_json(&self) -> String {
+        self.config.to_json()
+    }
+
+    #[wasm_bindgen]
+    pub fn validate(&self) -> Result<(), JsValue> {
+        self.config.validate().map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn tile_size(&self) -> f64 {
+        self.config.tile_size
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_tile_size(&mut self, tile_size: f64) {
+        self.config.tile_size = tile_size;
+    }
+
+    // 地图宽度（格子数），为 0 时按画布大小铺满
+    #[wasm_bindgen(getter)]
+    pub fn map_width(&self) -> u32 {
+        self.config.map_width
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_map_width(&mut self, map_width: u32) {
+        self.config.map_width = map_width;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn map_height(&self) -> u32 {
+        self.config.map_height
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_map_height(&mut self, map_height: u32) {
+        self.config.map_height = map_height;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn treasure_count(&self) -> u32 {
+        self.config.treasure_count
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_treasure_count(&mut self, treasure_count: u32) {
+        self.config.treasure_count = treasure_count;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn time_limit(&self) -> f64 {
+        self.config.time_limit
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_time_limit(&mut self, time_limit: f64) {
+        self.config.time_limit = time_limit;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn treasure_value(&self) -> u32 {
+        self.config.treasure_value
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_treasure_value(&mut self, treasure_value: u32) {
+        self.config.treasure_value = treasure_value;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn combo_window(&self) -> f64 {
+        self.config.combo_window
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_combo_window(&mut self, combo_window: f64) {
+        self.config.combo_window = combo_window;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn combo_decay(&self) -> f64 {
+        self.config.combo_decay
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_combo_decay(&mut self, combo_decay: f64) {
+        self.config.combo_decay = combo_decay;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn treasure_min_spread(&self) -> f64 {
+        self.config.treasure_min_spread
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_treasure_min_spread(&mut self, treasure_min_spread: f64) {
+        self.config.treasure_min_spread = treasure_min_spread;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn obstacle_density(&self) -> f64 {
+        self.config.obstacle_density
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_obstacle_density(&mut self, obstacle_density: f64) {
+        self.config.obstacle_density = obstacle_density;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn guard_count(&self) -> u32 {
+        self.config.guard_count
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_guard_count(&mut self, guard_count: u32) {
+        self.config.guard_count = guard_count;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn guard_speed(&self) -> f64 {
+        self.config.guard_speed
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_guard_speed(&mut self, guard_speed: f64) {
+        self.config.guard_speed = guard_speed;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn guard_view_range(&self) -> f64 {
+        self.config.guard_view_range
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_guard_view_range(&mut self, guard_view_range: f64) {
+        self.config.guard_view_range = guard_view_range;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn fog_of_war(&self) -> bool {
+        self.config.fog_of_war
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_fog_of_war(&mut self, fog_of_war: bool) {
+        self.config.fog_of_war = fog_of_war;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn view_radius(&self) -> f64 {
+        self.config.view_radius
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_view_radius(&mut self, view_radius: f64) {
+        self.config.view_radius = view_radius;
+    }
+
+    // 被抓到时扣除的秒数，被抓到直接结束这一局时为 0
+    #[wasm_bindgen(getter)]
+    pub fn guard_time_penalty(&self) -> f64 {
+        match self.config.guard_contact {
+            GuardContact::Penalty(seconds) => seconds,
+            GuardContact::EndRound => 0.0,
+        }
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_guard_time_penalty(&mut self, seconds: f64) {
+        self.config.guard_contact = GuardContact::Penalty(seconds);
+    }
+
+    // 被抓到时是否直接结束这一局
+    #[wasm_bindgen(getter)]
+    pub fn guard_ends_round(&self) -> bool {
+        self.config.guard_contact == GuardContact::EndRound
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_guard_ends_round(&mut self, ends_round: bool) {
+        self.config.guard_contact = if ends_round { GuardContact::EndRound } else { GuardContact::default() };
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn acceleration(&self) -> f64 {
+        self.config.movement.acceleration
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_acceleration(&mut self, acceleration: f64) {
+        self.config.movement.acceleration = acceleration;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_speed(&self) -> f64 {
+        self.config.movement.max_speed
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_max_speed(&mut self, max_speed: f64) {
+        self.config.movement.max_speed = max_speed;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn friction(&self) -> f64 {
+        self.config.movement.friction
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_friction(&mut self, friction: f64) {
+        self.config.movement.friction = friction;
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<simulation::GameState> for GameState {
+    fn from(state: simulation::GameState) -> Self {
+        match state {
+            simulation::GameState::Menu => GameState::Menu,
+            simulation::GameState::LevelIntro => GameState::LevelIntro,
+            simulation::GameState::Playing => GameState::Playing,
+            simulation::GameState::Paused => GameState::Paused,
+            simulation::GameState::Results => GameState::Results,
+            simulation::GameState::GameOver => GameState::GameOver,
+        }
+    }
 }
 
 #[wasm_bindgen]
 impl Game {
+    // 不传入种子时随机选择一个，可通过 seed() 获取以便复现同一张地图
+    // 不传入 config 时使用默认规则，规则不合理时返回错误
+    // 新版 wasm-bindgen 把 async 构造函数标记为 deprecated（生成的 TS 声明不正确），
+    // 但 index.js 依赖 `await new Game(...)`，改成工厂函数之前先保留
+    #[allow(deprecated)]
     #[wasm_bindgen(constructor)]
-    pub async fn new(width: u32, height: u32) -> Result<Game, JsValue> {
+    pub async fn new(width: u32, height: u32, seed: Option<u32>, config: Option<GameConfig>) -> Result<Game, JsValue> {
         console::log_1(&"Creating new game instance".into());
-        
-        // 初始化音频系统
-        let audio = match AudioSystem::new().await {
-            Ok(audio_system) => {
-                // 预加载所有音效
-                audio_system.load_sound("collect", "./sounds/collect.mp3").await?;
-                audio_system.load_sound("win", "./sounds/win.mp3").await?;
-                audio_system.load_sound("timeup", "./sounds/timeup.mp3").await?;
-                audio_system.load_sound("background", "./sounds/background.mp3").await?;
-                Some(audio_system)
-            },
-            Err(_) => None,
-        };
 
-        let tile_size = 40.0;
-        let map_width = (width as f64 / tile_size) as usize;
-        let map_height = (height as f64 / tile_size) as usize;
-        
-        let map = GameMap::new(map_width, map_height, tile_size);
-        let player = Player::new(tile_size * 1.5, tile_size * 1.5);
-        let mut treasure_manager = TreasureManager::new();
-        treasure_manager.generate_treasures(5, width as f64, height as f64, tile_size, &map);
-
-        Ok(Game {
+        let config = config.unwrap_or_default().config;
+        let simulation = Simulation::with_config(
             width,
             height,
-            is_running: false,
-            last_frame_time: 0.0,
-            player,
-            map,
-            treasure_manager,
-            keys_pressed: Vec::new(),
-            state: GameState::Playing,
-            game_time: 0.0,
-            time_limit: 60.0,
-            best_time: None,
-            first_timestamp: None,
-            audio,
-        })
+            seed.unwrap_or_else(random_seed),
+            MapGenerator::default(),
+            config,
+        ).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let audio = load_audio().await?;
+        let mut game = Game::with_simulation(simulation, audio);
+        game.set_viewport(width, height);
+        Ok(game)
+    }
+
+    // 从文本关卡创建游戏，格式错误时返回带行列号的错误信息
+    #[wasm_bindgen]
+    pub async fn from_level_string(text: String, config: Option<GameConfig>) -> Result<Game, JsValue> {
+        console::log_1(&"Creating game from level".into());
+
+        let config = config.unwrap_or_default().config;
+        config.validate().map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let level = Level::parse(&text, config.tile_size)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let mut simulation = Simulation::from_level(level);
+        simulation.set_config(config).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let audio = load_audio().await?;
+        Ok(Game::with_simulation(simulation, audio))
+    }
+
+    // 更换游戏规则并回到菜单
+    #[wasm_bindgen]
+    pub fn set_config(&mut self, config: &GameConfig) -> Result<(), JsValue> {
+        self.simulation.set_config(config.config).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.keys_pressed.clear();
+        self.flush_events();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_config(&self) -> GameConfig {
+        GameConfig { config: *self.simulation.config() }
+    }
+
+    // 在现有游戏中载入文本关卡
+    #[wasm_bindgen]
+    pub fn load_level_string(&mut self, text: &str) -> Result<(), JsValue> {
+        let level = Level::parse(text, self.simulation.config().tile_size)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.simulation.load_level(level);
+        self.keys_pressed.clear();
+        self.flush_events();
+        Ok(())
     }
 
-    // 添加 #[wasm_bindgen] 属性给这些公共方法
+    // 把当前地图导出为文本关卡
+    #[wasm_bindgen]
+    pub fn to_level_string(&self) -> String {
+        self.simulation.to_level().to_level_string()
+    }
+
+    // 从菜单或结算画面开始新的一局，先播放开场倒计时
     #[wasm_bindgen]
     pub fn start(&mut self) {
-        console::log_1(&"Game started!".into());
-        self.is_running = true;
-        self.last_frame_time = 0.0;
-        self.game_time = 0.0;
-        self.time_limit = 60.0;
-        if let Some(audio) = &self.audio {
-            audio.play_music("bgm");
+        if self.simulation.start() {
+            console::log_1(&"Game started!".into());
+            self.last_frame_time = 0.0;
+            self.keys_pressed.clear();
         }
-        console::log_1(&format!("Start: time_limit={}, game_time={}", 
-            self.time_limit, self.game_time).into());
+        self.flush_events();
     }
 
+    // 结束当前这一局并回到菜单
     #[wasm_bindgen]
     pub fn stop(&mut self) {
-        console::log_1(&"Game stopped!".into());
-        self.is_running = false;
-        
-        if let Some(audio) = &self.audio {
-            audio.stop_music();
+        if self.simulation.stop() {
+            console::log_1(&"Game stopped!".into());
+            self.keys_pressed.clear();
         }
+        self.flush_events();
+    }
+
+    // 暂停游戏，保留已经过的时间并暂停背景音乐
+    #[wasm_bindgen]
+    pub fn pause(&mut self) {
+        if self.simulation.pause() {
+            console::log_1(&"Game paused!".into());
+            self.keys_pressed.clear();
+        }
+        self.flush_events();
+    }
+
+    // 从暂停处继续
+    #[wasm_bindgen]
+    pub fn resume(&mut self) {
+        if self.simulation.resume() {
+            console::log_1(&"Game resumed!".into());
+            // 丢弃暂停期间的真实时间，下一帧只记录时间戳
+            self.last_frame_time = 0.0;
+        }
+        self.flush_events();
+    }
+
+    #[wasm_bindgen]
+    pub fn is_paused(&self) -> bool {
+        self.simulation.is_paused()
     }
 
     // 更新游戏状态检查
     #[wasm_bindgen]
     pub fn update(&mut self, timestamp: f64) {
-        if !self.is_running {
+        if !self.simulation.is_running() {
             return;
         }
 
@@ -129,104 +455,287 @@ impl Game {
         };
 
         self.last_frame_time = timestamp;
-        
-        // 防止游戏时间出现 NaN
-        if !self.game_time.is_nan() {
-            self.game_time += delta_time;
-        } else {
-            self.game_time = 0.0;
-        }
 
-        // 先检查时间限制
-        if self.game_time >= self.time_limit {
-            self.state = GameState::TimeUp;
-            if let Some(audio) = &self.audio {
-                audio.stop_music();
-                audio.play_sound("timeup");
-            }
-            self.stop();
-            return;
-        }
+        self.simulation.set_input(self.current_input());
+        let events = self.simulation.update(delta_time);
+        self.handle_events(&events);
 
-        // 更新玩家位置和收集宝藏
-        self.update_player();
+        // 摄像机跟随插值后的玩家位置，画面不会随模拟步长抖动
+        let (x, y) = self.player_center();
+        let world = self.world_size();
+        self.camera.follow((x, y), world, delta_time);
+    }
 
-        // 检查胜利条件
-        if self.treasure_manager.all_treasures_collected() {
-            self.state = GameState::Won;
-            // 更新最佳时间
-            if let Some(best_time) = self.best_time {
-                if self.game_time < best_time {
-                    self.best_time = Some(self.game_time);
-                }
-            } else {
-                self.best_time = Some(self.game_time);
-            }
+    // 画布（视口）大小，地图比画布大时摄像机跟随玩家滚动
+    #[wasm_bindgen]
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        self.camera.set_viewport(width as f64, height as f64);
+        self.snap_camera();
+    }
 
-            if let Some(audio) = &self.audio {
-                audio.stop_music();
-                audio.play_sound("win");
-            }
+    // 缩放倍数（0.25 到 4），大于 1 时放大
+    #[wasm_bindgen]
+    pub fn set_camera_zoom(&mut self, zoom: f64) {
+        self.camera.set_zoom(zoom);
+        self.snap_camera();
+    }
+
+    #[wasm_bindgen]
+    pub fn get_camera_zoom(&self) -> f64 {
+        self.camera.zoom()
+    }
+
+    // 视口中央玩家可以自由移动而摄像机不动的区域占视口的比例（0 到 1）
+    #[wasm_bindgen]
+    pub fn set_camera_deadzone(&mut self, deadzone: f64) {
+        self.camera.set_deadzone(deadzone);
+    }
+
+    // 摄像机跟随的平滑系数（每秒），为 0 时不平滑
+    #[wasm_bindgen]
+    pub fn set_camera_smoothing(&mut self, smoothing: f64) {
+        self.camera.set_smoothing(smoothing);
+    }
+
+    // 视口左上角的世界坐标（像素）
+    #[wasm_bindgen]
+    pub fn get_camera_x(&self) -> f64 {
+        self.camera.x()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_camera_y(&self) -> f64 {
+        self.camera.y()
+    }
+
+    // 设置每秒模拟步数（默认 60，限制在 10 到 1000 之间），与显示器刷新率无关
+    #[wasm_bindgen]
+    pub fn set_tick_rate(&mut self, tick_rate: f64) {
+        self.simulation.set_tick_rate(tick_rate);
+    }
 
-            self.stop();
+    // 调整玩家移动手感：加速度和摩擦力单位是像素/秒²，最大速度单位是像素/秒
+    #[wasm_bindgen]
+    pub fn set_player_movement(&mut self, acceleration: f64, max_speed: f64, friction: f64) -> Result<(), JsValue> {
+        if [acceleration, max_speed, friction].iter().any(|v| !v.is_finite() || *v <= 0.0) {
+            return Err(JsValue::from_str("movement values must be positive numbers"));
         }
+        self.simulation.set_player_movement(Movement { acceleration, max_speed, friction });
+        Ok(())
     }
 
-    // 重置游戏
+    // 两次模拟步之间的插值比例，用于平滑绘制
     #[wasm_bindgen]
-    pub fn reset(&mut self) {
-        self.game_time = 0.0;
-        self.state = GameState::Playing;
-        self.is_running = false;
-        
-        // 重置玩家到左上角固定位置
-        let tile_size = 40.0;
-        self.player = Player::new(tile_size * 1.5, tile_size * 1.5); 
-        
+    pub fn get_interpolation_alpha(&self) -> f64 {
+        self.simulation.interpolation_alpha()
+    }
+
+    // 重置游戏，地图和宝藏按新种子重新生成（不传入种子时随机选择）
+    #[wasm_bindgen]
+    pub fn reset(&mut self, seed: Option<u32>) {
+        self.simulation.reset(seed.unwrap_or_else(random_seed));
+        self.flush_events();
+
         // 重置按键状态
         self.keys_pressed.clear();
 
-        // 重置分数
-        self.treasure_manager.reset_score();
-
         // 重置初始时间戳
         self.first_timestamp = None;
-        
-        // 重新生成宝藏
-        self.treasure_manager.generate_treasures(
-            5,
-            self.width as f64,
-            self.height as f64,
-            40.0,
-            &self.map
-        );
+    }
+
+    // 选择地图生成方式，下一次 reset 时生效
+    // name 可以是 "obstacles"、"cave"、"dungeon"、"backtracker"、"prim"、"kruskal"；loop_ratio 大于 0 时生成带环路的迷宫
+    #[wasm_bindgen]
+    pub fn set_map_generator(&mut self, name: &str, loop_ratio: Option<f64>) -> Result<(), JsValue> {
+        let generator = if name == "obstacles" {
+            MapGenerator::Obstacles
+        } else if name == "cave" {
+            MapGenerator::Cave(CaveSettings::default())
+        } else if name == "dungeon" {
+            MapGenerator::Dungeon(DungeonSettings::default())
+        } else {
+            let algorithm = MazeAlgorithm::from_name(name)
+                .ok_or_else(|| JsValue::from_str(&format!("Unknown map generator: {}", name)))?;
+            match loop_ratio {
+                Some(loop_ratio) if loop_ratio > 0.0 => MapGenerator::BraidedMaze { algorithm, loop_ratio },
+                _ => MapGenerator::Maze(algorithm),
+            }
+        };
+        self.simulation.set_map_generator(generator);
+        Ok(())
+    }
+
+    // 选择难度："easy"、"normal"、"hard"、"expert"，需要在 start() 之前调用
+    #[wasm_bindgen]
+    pub fn set_difficulty(&mut self, name: &str) -> Result<(), JsValue> {
+        let difficulty = Difficulty::from_name(name)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown difficulty: {}", name)))?;
+        if !self.simulation.set_difficulty(difficulty) {
+            return Err(JsValue::from_str("Difficulty can only be changed before the game starts"));
+        }
+        self.keys_pressed.clear();
+        self.flush_events();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_difficulty(&self) -> String {
+        self.simulation.difficulty().name().to_string()
+    }
+
+    // 指定难度的最佳时间
+    #[wasm_bindgen]
+    pub fn get_best_time_for(&self, name: &str) -> Result<Option<f64>, JsValue> {
+        let difficulty = Difficulty::from_name(name)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown difficulty: {}", name)))?;
+        Ok(self.simulation.best_time_for(difficulty))
+    }
+
+    // 开始内置战役，回到菜单并从第一关开始；之后 set_map_generator 或载入关卡会退出战役
+    #[wasm_bindgen]
+    pub fn start_campaign(&mut self) {
+        let map = self.simulation.map();
+        let campaign = Campaign::standard(map.width(), map.height());
+        self.simulation.set_campaign(campaign);
+        self.keys_pressed.clear();
+        self.flush_events();
+    }
+
+    // 当前关卡序号，从 0 开始
+    #[wasm_bindgen]
+    pub fn current_level(&self) -> usize {
+        self.simulation.current_level()
+    }
+
+    #[wasm_bindgen]
+    pub fn level_count(&self) -> usize {
+        self.simulation.level_count()
+    }
+
+    // 过关后是否还有下一关，此时调用 start() 进入下一关
+    #[wasm_bindgen]
+    pub fn has_next_level(&self) -> bool {
+        self.simulation.has_next_level()
+    }
+
+    // 在现有游戏中载入 Tiled 编辑器导出的 JSON 地图（.tmj）
+    #[wasm_bindgen]
+    pub fn load_tiled_json(&mut self, json: &str) -> Result<(), JsValue> {
+        let level = tiled::import_tiled_json(json, self.simulation.config().tile_size)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.simulation.load_level(level);
+        self.keys_pressed.clear();
+        self.flush_events();
+        Ok(())
+    }
+
+    // 把当前游戏保存为 JSON 字符串
+    #[wasm_bindgen]
+    pub fn save_state(&self) -> String {
+        self.simulation.save_state().to_json()
+    }
+
+    // 从 save_state 得到的 JSON 恢复游戏
+    #[wasm_bindgen]
+    pub fn load_state(&mut self, json: &str) -> Result<(), JsValue> {
+        let save = SaveState::from_json(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.simulation.restore(save).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.keys_pressed.clear();
+        self.sync_audio();
+        self.snap_camera();
+        // 丢弃保存前的时间戳，避免恢复后第一帧出现巨大的时间差
+        self.last_frame_time = 0.0;
+        Ok(())
+    }
+
+    // 把当前地图导出为 JSON 关卡
+    #[wasm_bindgen]
+    pub fn to_level_json(&self) -> String {
+        LevelFile::new(self.simulation.to_level()).to_json()
+    }
+
+    // 在现有游戏中载入 JSON 关卡
+    #[wasm_bindgen]
+    pub fn load_level_json(&mut self, json: &str) -> Result<(), JsValue> {
+        let level = LevelFile::from_json(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.simulation.load_level(level);
+        self.keys_pressed.clear();
+        self.flush_events();
+        Ok(())
+    }
+
+    // 获取当前地图的种子
+    #[wasm_bindgen]
+    pub fn seed(&self) -> u32 {
+        self.simulation.seed()
     }
 
     // 获取游戏状态
     #[wasm_bindgen]
     pub fn get_state(&self) -> GameState {
-        self.state
+        self.simulation.state().into()
+    }
+
+    // 最近一局失败的原因："time_up" 或 "caught"（被守卫抓到直接结束）
+    #[wasm_bindgen]
+    pub fn get_game_over_reason(&self) -> String {
+        self.simulation.game_over_reason().name().to_string()
+    }
+
+    // 设置记入排行榜的玩家名字，空名字记为 "Player"
+    #[wasm_bindgen]
+    pub fn set_player_name(&mut self, name: &str) {
+        let name: String = name.trim().chars().take(MAX_PLAYER_NAME_LENGTH).collect();
+        self.player_name = if name.is_empty() { "Player".to_string() } else { name };
+    }
+
+    #[wasm_bindgen]
+    pub fn get_player_name(&self) -> String {
+        self.player_name.clone()
+    }
+
+    // 排行榜的 JSON 数组，每条记录有 name、score、time、seed、difficulty、date
+    #[wasm_bindgen]
+    pub fn get_leaderboard_json(&self) -> String {
+        serde_json::to_string(self.leaderboard.entries()).expect("leaderboard is always serializable")
+    }
+
+    // 最近一局在排行榜上的名次（从 0 开始），没有进入排行榜时为 undefined
+    #[wasm_bindgen]
+    pub fn get_last_rank(&self) -> Option<usize> {
+        self.last_rank
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_leaderboard(&mut self) -> Result<(), JsValue> {
+        self.leaderboard.clear();
+        self.last_rank = None;
+        self.leaderboard.save(self.storage.as_mut(), LEADERBOARD_KEY)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    // 开场倒计时剩余的秒数
+    #[wasm_bindgen]
+    pub fn get_intro_remaining(&self) -> f64 {
+        self.simulation.intro_remaining()
     }
 
     // 获取当前游戏时间
     #[wasm_bindgen]
     pub fn get_game_time(&self) -> f64 {
-        self.game_time
+        self.simulation.game_time()
     }
 
     // 获取剩余时间
     #[wasm_bindgen]
     pub fn get_remaining_time(&self) -> f64 {
-        let remaining = self.time_limit - self.game_time;
-        console::log_1(&format!("Calculating remaining time: {} - {} = {}", 
-            self.time_limit, self.game_time, remaining).into());
-        remaining.max(0.0)
+        self.simulation.remaining_time()
     }
 
-    // 获取最佳时间
+    // 获取当前难度的最佳时间
     #[wasm_bindgen]
     pub fn get_best_time(&self) -> Option<f64> {
-        self.best_time
+        self.simulation.best_time()
     }
 
     // 添加时间戳设置方法
@@ -235,7 +744,7 @@ impl Game {
         if self.first_timestamp.is_none() {
             self.first_timestamp = Some(timestamp);
         }
-    }    
+    }
 
     #[wasm_bindgen]
     pub fn handle_key_down(&mut self, key: String) {
@@ -251,99 +760,394 @@ impl Game {
 
     #[wasm_bindgen]
     pub fn is_running(&self) -> bool {
-        self.is_running
+        self.simulation.is_running()
     }
 
+    // 玩家信息
     #[wasm_bindgen]
-    pub fn player(&self) -> Player {
-        self.player
+    pub fn get_player_x(&self) -> f64 {
+        self.simulation.player().x()
     }
 
-    // 内部方法不需要 #[wasm_bindgen]
-    pub fn update_player(&mut self) {
-        let mut new_x = self.player.x();
-        let mut new_y = self.player.y();
-        let speed = 5.0;
-
-        for key in &self.keys_pressed {
-            match key.as_str() {
-                "ArrowUp" => new_y -= speed,
-                "ArrowDown" => new_y += speed,
-                "ArrowLeft" => new_x -= speed,
-                "ArrowRight" => new_x += speed,
-                _ => {}
-            }
-        }
-
-        // 碰撞检测
-        if self.map.is_walkable(new_x, new_y) 
-            && self.map.is_walkable(new_x + self.player.size(), new_y)
-            && self.map.is_walkable(new_x, new_y + self.player.size())
-            && self.map.is_walkable(new_x + self.player.size(), new_y + self.player.size()) {
-            self.player = Player::new(new_x, new_y);
-        }
+    #[wasm_bindgen]
+    pub fn get_player_y(&self) -> f64 {
+        self.simulation.player().y()
+    }
 
-        if self.treasure_manager.check_collection(
-            self.player.x(),
-            self.player.y(),
-            self.player.size()
-        ) {
-            console::log_1(&"Treasure collected!".into());
-            
-            if let Some(audio) = &self.audio {
-                audio.play_sound("collect");
-            }
-        }
+    #[wasm_bindgen]
+    pub fn get_player_size(&self) -> f64 {
+        self.simulation.player().size()
     }
-    
+
     // 添加为了map的方法
     #[wasm_bindgen]
     pub fn get_map_width(&self) -> usize {
-        self.map.width()
+        self.simulation.map().width()
     }
 
     #[wasm_bindgen]
     pub fn get_map_height(&self) -> usize {
-        self.map.height()
+        self.simulation.map().height()
     }
 
     #[wasm_bindgen]
     pub fn get_map_tile_size(&self) -> f64 {
-        self.map.tile_size()
+        self.simulation.map().tile_size()
+    }
+
+    #[wasm_bindgen]
+    pub fn is_tile_explored(&self, x: usize, y: usize) -> bool {
+        self.simulation.map().is_explored(x, y)
+    }
+
+    #[wasm_bindgen]
+    pub fn is_tile_visible(&self, x: usize, y: usize) -> bool {
+        self.simulation.map().is_visible(x, y)
+    }
+
+    // 已探索的空地比例（0 到 1）
+    #[wasm_bindgen]
+    pub fn get_exploration(&self) -> f64 {
+        self.simulation.exploration()
     }
 
     #[wasm_bindgen]
     pub fn get_map_tile(&self, x: usize, y: usize) -> i32 {
-        match self.map.get_tile(x, y) {
+        match self.simulation.map().get_tile(x, y) {
             TileType::Empty => 0,
             TileType::Wall => 1,
             TileType::Obstacle => 2,
         }
     }
 
+    // 两个格子之间的最短路径，from 和 to 是 [x, y] 格子坐标，
+    // 返回 [x0, y0, x1, y1, ...]（包含两端），走不到时返回空数组
+    // diagonal 为 true 时允许斜着走（不会贴着障碍物的角穿过去）
+    #[wasm_bindgen]
+    pub fn find_path(&self, from: &[u32], to: &[u32], diagonal: Option<bool>) -> Result<Vec<u32>, JsValue> {
+        let (&[from_x, from_y], &[to_x, to_y]) = (from, to) else {
+            return Err(JsValue::from_str("from and to must be [x, y] tile coordinates"));
+        };
+        let connectivity = if diagonal.unwrap_or(false) { Connectivity::Eight } else { Connectivity::Four };
+        let path = pathfinding::find_path(
+            self.simulation.map(),
+            (from_x as usize, from_y as usize),
+            (to_x as usize, to_y as usize),
+            connectivity,
+        );
+        Ok(path.unwrap_or_default()
+            .into_iter()
+            .flat_map(|(x, y)| [x as u32, y as u32])
+            .collect())
+    }
+
     // 为treasure添加方法
     #[wasm_bindgen]
     pub fn get_score(&self) -> u32 {
-        self.treasure_manager.get_score()
+        self.simulation.score()
+    }
+
+    // 当前连击倍率（1 表示没有连击）
+    #[wasm_bindgen]
+    pub fn get_combo_multiplier(&self) -> f64 {
+        self.simulation.treasures().combo().multiplier()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_combo_chain(&self) -> u32 {
+        self.simulation.treasures().combo().chain()
+    }
+
+    // 连击窗口剩余的比例（0 到 1）
+    #[wasm_bindgen]
+    pub fn get_combo_remaining(&self) -> f64 {
+        self.simulation.treasures().combo().remaining()
+    }
+
+    // 玩家到最近的需要收集的宝藏还要走多远（格子数），走不到时返回 undefined
+    #[wasm_bindgen]
+    pub fn get_treasure_distance(&self) -> Option<f64> {
+        self.simulation.treasure_distance()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_hint_remaining(&self) -> f64 {
+        self.simulation.hint_remaining()
+    }
+
+    // 指南针生效时指向最近宝藏的方向 [x, y]，否则返回 undefined
+    #[wasm_bindgen]
+    pub fn get_hint_direction(&self) -> Option<Vec<f64>> {
+        self.simulation.hint_direction().map(|(x, y)| vec![x, y])
+    }
+
+    #[wasm_bindgen]
+    pub fn get_guard_count(&self) -> usize {
+        self.simulation.guards().len()
     }
 
     #[wasm_bindgen]
     pub fn get_treasure_count(&self) -> usize {
-        self.treasure_manager.get_treasure_count()
+        self.simulation.treasures().get_treasure_count()
     }
 
     #[wasm_bindgen]
     pub fn get_treasure_x(&self, index: usize) -> Option<f64> {
-        self.treasure_manager.get_treasure_x(index)
+        self.simulation.treasures().get_treasure_x(index)
     }
 
     #[wasm_bindgen]
     pub fn get_treasure_y(&self, index: usize) -> Option<f64> {
-        self.treasure_manager.get_treasure_y(index)
+        self.simulation.treasures().get_treasure_y(index)
+    }
+
+    // 宝藏种类："coin"、"gem"、"chest"、"hourglass"、"cursed"
+    #[wasm_bindgen]
+    pub fn get_treasure_kind(&self, index: usize) -> Option<String> {
+        self.simulation.treasures().get_treasure_kind(index).map(|kind| kind.name().to_string())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_treasure_size(&self, index: usize) -> Option<f64> {
+        self.simulation.treasures().get_treasure_size(index)
     }
 
     #[wasm_bindgen]
     pub fn is_treasure_collected(&self, index: usize) -> bool {
-        self.treasure_manager.is_treasure_collected(index)
-    }    
-}
\ No newline at end of file
+        self.simulation.treasures().is_treasure_collected(index)
+    }
+}
+
+// 内部方法不需要 #[wasm_bindgen]
+impl Game {
+    // 视口大小默认与地图相同，可以用 set_viewport 修改
+    fn with_simulation(simulation: Simulation, audio: Option<AudioSystem>) -> Game {
+        // 浏览器不支持 localStorage 时排行榜只保存在内存中
+        let storage: Box<dyn Storage> = match LocalStorage::new() {
+            Some(storage) => Box::new(storage),
+            None => Box::new(MemoryStorage::new()),
+        };
+        let leaderboard = Leaderboard::load(storage.as_ref(), LEADERBOARD_KEY, LEADERBOARD_SIZE);
+
+        let mut game = Game {
+            last_frame_time: 0.0,
+            keys_pressed: Vec::new(),
+            first_timestamp: None,
+            audio,
+            storage,
+            leaderboard,
+            player_name: "Player".to_string(),
+            last_rank: None,
+            camera: Camera::new(simulation.width() as f64, simulation.height() as f64),
+            simulation,
+        };
+        game.snap_camera();
+        game
+    }
+
+    pub(crate) fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    // 地图的像素大小
+    fn world_size(&self) -> (f64, f64) {
+        let map = self.simulation.map();
+        (map.width() as f64 * map.tile_size(), map.height() as f64 * map.tile_size())
+    }
+
+    fn player_center(&self) -> (f64, f64) {
+        let (x, y) = self.simulation.interpolated_player_position(self.simulation.interpolation_alpha());
+        let half = self.simulation.player().size() / 2.0;
+        (x + half, y + half)
+    }
+
+    // 摄像机直接对准玩家，用于开局、换地图和读档
+    fn snap_camera(&mut self) {
+        let target = self.player_center();
+        let world = self.world_size();
+        self.camera.snap_to(target, world);
+    }
+
+    pub(crate) fn simulation(&self) -> &Simulation {
+        &self.simulation
+    }
+
+    pub(crate) fn leaderboard(&self) -> &Leaderboard {
+        &self.leaderboard
+    }
+
+    // 通关（战役中是打完最后一关）后把成绩记入排行榜并保存
+    fn record_score(&mut self) {
+        if self.simulation.has_next_level() {
+            return;
+        }
+        let entry = LeaderboardEntry {
+            name: self.player_name.clone(),
+            score: self.simulation.score(),
+            time: self.simulation.game_time(),
+            seed: self.simulation.seed(),
+            difficulty: self.simulation.difficulty(),
+            date: js_sys::Date::now(),
+        };
+        self.last_rank = self.leaderboard.add(entry);
+        if self.last_rank.is_some() {
+            if let Err(e) = self.leaderboard.save(self.storage.as_mut(), LEADERBOARD_KEY) {
+                console::error_1(&format!("Failed to save leaderboard: {}", e).into());
+            }
+        }
+    }
+
+    // 把浏览器按键名转换为方向输入
+    fn current_input(&self) -> Input {
+        let mut input = Input::default();
+        for key in &self.keys_pressed {
+            match key.as_str() {
+                "ArrowUp" => input.up = true,
+                "ArrowDown" => input.down = true,
+                "ArrowLeft" => input.left = true,
+                "ArrowRight" => input.right = true,
+                _ => {}
+            }
+        }
+        input
+    }
+
+    // 处理 start、pause 等调用产生的事件
+    // 这些调用可能换了地图或者把玩家放回出生点，摄像机也重新对准玩家
+    fn flush_events(&mut self) {
+        let events = self.simulation.take_events();
+        self.handle_events(&events);
+        self.snap_camera();
+    }
+
+    // 把核心逻辑产生的事件转换为音效、日志和排行榜记录
+    fn handle_events(&mut self, events: &[GameEvent]) {
+        for event in events {
+            match *event {
+                GameEvent::TreasureCollected { kind, points, chain } => {
+                    console::log_1(&format!("Treasure collected: {} ({:+}, combo {})", kind.name(), points, chain).into());
+                    if let Some(audio) = &self.audio {
+                        audio.play_sound_at_rate("collect", collect_sound_rate(kind) * combo_pitch(chain));
+                    }
+                }
+                GameEvent::GuardAlerted => {
+                    console::log_1(&"A guard spotted you!".into());
+                    if let Some(audio) = &self.audio {
+                        audio.play_sound_at_rate("collect", 0.4);
+                    }
+                }
+                GameEvent::PlayerCaught { contact } => {
+                    if let GuardContact::Penalty(seconds) = contact {
+                        console::log_1(&format!("Caught by a guard: -{:.0}s", seconds).into());
+                        if let Some(audio) = &self.audio {
+                            audio.play_sound_at_rate("timeup", 1.5);
+                        }
+                    } else {
+                        console::log_1(&"Caught by a guard!".into());
+                    }
+                }
+                GameEvent::StateChanged { from, to } => {
+                    match to {
+                        simulation::GameState::Results => self.record_score(),
+                        simulation::GameState::LevelIntro => self.last_rank = None,
+                        _ => {}
+                    }
+                    self.on_state_changed(from, to);
+                }
+            }
+        }
+    }
+
+    // 读档后没有状态切换事件，直接让音乐与恢复的状态一致
+    fn sync_audio(&self) {
+        let Some(audio) = &self.audio else {
+            return;
+        };
+        match self.simulation.state() {
+            simulation::GameState::LevelIntro | simulation::GameState::Playing => {
+                audio.resume_music();
+                audio.play_music("background");
+            }
+            // 先开始播放再挂起，继续游戏时音乐接着播放
+            simulation::GameState::Paused => {
+                audio.play_music("background");
+                audio.pause_music();
+            }
+            simulation::GameState::Menu | simulation::GameState::Results | simulation::GameState::GameOver => {
+                audio.resume_music();
+                audio.stop_music();
+            }
+        }
+    }
+
+    // 音频跟随状态机切换
+    fn on_state_changed(&self, from: simulation::GameState, to: simulation::GameState) {
+        let Some(audio) = &self.audio else {
+            return;
+        };
+        match to {
+            simulation::GameState::Menu => {
+                audio.stop_music();
+                // 从暂停回到菜单时音频上下文仍是挂起的
+                if from == simulation::GameState::Paused {
+                    audio.resume_music();
+                }
+            }
+            simulation::GameState::LevelIntro => {
+                audio.resume_music();
+                audio.play_music("background");
+            }
+            simulation::GameState::Playing => {
+                if from == simulation::GameState::Paused {
+                    audio.resume_music();
+                }
+            }
+            simulation::GameState::Paused => audio.pause_music(),
+            simulation::GameState::Results => {
+                audio.stop_music();
+                audio.play_sound("win");
+            }
+            simulation::GameState::GameOver => {
+                audio.stop_music();
+                audio.play_sound("timeup");
+            }
+        }
+    }
+}
+
+// 初始化音频系统，浏览器不支持时返回 None
+async fn load_audio() -> Result<Option<AudioSystem>, JsValue> {
+    match AudioSystem::new().await {
+        Ok(audio_system) => {
+            // 预加载所有音效
+            audio_system.load_sound("collect", "./sounds/collect.mp3").await?;
+            audio_system.load_sound("win", "./sounds/win.mp3").await?;
+            audio_system.load_sound("timeup", "./sounds/timeup.mp3").await?;
+            audio_system.load_sound("background", "./sounds/background.mp3").await?;
+            Ok(Some(audio_system))
+        },
+        Err(_) => Ok(None),
+    }
+}
+
+// 不同种类的宝藏用不同音调播放收集音效，被诅咒的宝藏声音低沉
+fn collect_sound_rate(kind: TreasureKind) -> f32 {
+    match kind {
+        TreasureKind::Coin => 1.0,
+        TreasureKind::Gem => 1.3,
+        TreasureKind::Chest => 0.8,
+        TreasureKind::Hourglass => 1.6,
+        TreasureKind::Cursed => 0.5,
+        TreasureKind::Compass => 1.1,
+    }
+}
+
+// 连击越长音调越高，每多一个升两个半音，最多升一个八度
+fn combo_pitch(chain: u32) -> f32 {
+    let semitones = (chain.saturating_sub(1) * 2).min(12);
+    2f32.powf(semitones as f32 / 12.0)
+}
+
+fn random_seed() -> u32 {
+    (js_sys::Math::random() * u32::MAX as f64) as u32
+}
@@ -0,0 +1,325 @@
+// src/guard.rs
+// 巡逻的守卫：沿路线在相邻的空地之间来回走动，看到玩家后追赶，
+// 跟丢后在最后看到玩家的地方停留一会儿，再沿最短路径回到路线上
+use serde::{Deserialize, Serialize};
+
+use crate::map::GameMap;
+use crate::pathfinding::{self, Connectivity};
+use crate::rng::Rng;
+
+// 守卫边长（像素）
+pub const GUARD_SIZE: f64 = 20.0;
+
+// 巡逻路线最多经过的格子数
+const ROUTE_LENGTH: usize = 10;
+
+// 守卫出生点与玩家出生点的最小距离（格子数）
+const MIN_SPAWN_DISTANCE: f64 = 6.0;
+
+// 视野半角的余弦（60 度），追赶时不受视野角度限制
+const VIEW_COS: f64 = 0.5;
+
+// 追赶时的速度倍数
+const CHASE_SPEED_FACTOR: f64 = 1.3;
+
+// 跟丢后在最后看到玩家的位置停留的秒数
+const SEARCH_DURATION: f64 = 2.0;
+
+// 抓到玩家后守卫停下的秒数，避免每一步都重复扣时间
+const STUN_DURATION: f64 = 2.0;
+
+// 抓到玩家的后果
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GuardContact {
+    // 时间限制减少若干秒
+    Penalty(f64),
+    // 直接结束这一局
+    EndRound,
+}
+
+impl Default for GuardContact {
+    fn default() -> Self {
+        GuardContact::Penalty(10.0)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GuardMode {
+    Patrol,
+    Chase,
+    // 跟丢后在最后看到玩家的位置停留
+    Search { remaining: f64 },
+    // 沿最短路径回到巡逻路线
+    Return,
+    Stunned { remaining: f64 },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Guard {
+    // 左上角坐标，与 Player 相同
+    x: f64,
+    y: f64,
+    // 巡逻速度（像素/秒）
+    speed: f64,
+    // 视野距离（像素）
+    view_range: f64,
+    // 面朝的方向（单位向量）
+    facing: (f64, f64),
+    // 巡逻路线上的格子，相邻两个格子上下左右相邻，走到头后原路返回
+    route: Vec<(usize, usize)>,
+    route_index: usize,
+    route_forward: bool,
+    mode: GuardMode,
+    // 最后看到玩家的位置（玩家中心）
+    last_seen: (f64, f64),
+    // 回到巡逻路线要经过的格子，倒序存放，最后一个是下一个要去的格子
+    #[serde(default)]
+    path: Vec<(usize, usize)>,
+}
+
+impl Guard {
+    pub fn new(route: Vec<(usize, usize)>, tile_size: f64, speed: f64, view_range: f64) -> Guard {
+        assert!(!route.is_empty(), "guard route needs at least one tile");
+        let (x, y) = tile_origin(route[0], tile_size);
+        Guard {
+            x,
+            y,
+            speed,
+            view_range,
+            facing: (1.0, 0.0),
+            route,
+            route_index: 0,
+            route_forward: true,
+            mode: GuardMode::Patrol,
+            last_seen: (0.0, 0.0),
+            path: Vec::new(),
+        }
+    }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn size(&self) -> f64 {
+        GUARD_SIZE
+    }
+
+    pub fn facing(&self) -> (f64, f64) {
+        self.facing
+    }
+
+    pub fn view_range(&self) -> f64 {
+        self.view_range
+    }
+
+    pub fn mode(&self) -> GuardMode {
+        self.mode
+    }
+
+    pub fn route(&self) -> &[(usize, usize)] {
+        &self.route
+    }
+
+    // 存档中的守卫可能来自被修改过的文件：路线不能为空，路线和回程路径上的格子都要在地图内
+    pub fn is_consistent(&self, map: &GameMap) -> bool {
+        let inside = |&(x, y): &(usize, usize)| x < map.width() && y < map.height();
+        self.route_index < self.route.len()
+            && self.route.iter().all(inside)
+            && self.path.iter().all(inside)
+    }
+
+    pub fn is_chasing(&self) -> bool {
+        self.mode == GuardMode::Chase
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        matches!(self.mode, GuardMode::Stunned { .. })
+    }
+
+    fn center(&self) -> (f64, f64) {
+        (self.x + GUARD_SIZE / 2.0, self.y + GUARD_SIZE / 2.0)
+    }
+
+    // 玩家中心是否在视野内：距离不超过视野距离，在面朝方向的视野角度内（追赶时不限），
+    // 并且中间没有墙或障碍物挡住
+    pub fn can_see(&self, map: &GameMap, target: (f64, f64)) -> bool {
+        let (x, y) = self.center();
+        let (dx, dy) = (target.0 - x, target.1 - y);
+        let distance = dx.hypot(dy);
+        if distance > self.view_range {
+            return false;
+        }
+        // 贴得很近时背对着也能发现
+        let in_view = self.mode == GuardMode::Chase
+            || distance < map.tile_size()
+            || (dx * self.facing.0 + dy * self.facing.1) / distance >= VIEW_COS;
+        in_view && map.has_line_of_sight((x, y), target)
+    }
+
+    // 推进一步，target 是玩家中心，返回这一步是否刚刚发现玩家
+    pub fn update(&mut self, map: &GameMap, target: (f64, f64), step: f64) -> bool {
+        if let GuardMode::Stunned { remaining } = self.mode {
+            let remaining = remaining - step;
+            if remaining > 0.0 {
+                self.mode = GuardMode::Stunned { remaining };
+            } else {
+                self.start_return(map);
+            }
+            return false;
+        }
+
+        let spotted = self.can_see(map, target);
+        let alerted = spotted && self.mode != GuardMode::Chase;
+        if spotted {
+            self.mode = GuardMode::Chase;
+            self.last_seen = target;
+        }
+
+        match self.mode {
+            GuardMode::Patrol => self.patrol(map, step),
+            GuardMode::Chase => {
+                let (x, y) = (self.x, self.y);
+                let reached = self.move_towards(map, self.last_seen, self.speed * CHASE_SPEED_FACTOR * step);
+                // 到达（或被卡在）最后看到玩家的位置仍然看不到玩家，开始原地搜索
+                if !spotted && (reached || (self.x == x && self.y == y)) {
+                    self.mode = GuardMode::Search { remaining: SEARCH_DURATION };
+                }
+            }
+            GuardMode::Search { remaining } => {
+                let remaining = remaining - step;
+                if remaining > 0.0 {
+                    self.mode = GuardMode::Search { remaining };
+                } else {
+                    self.start_return(map);
+                }
+            }
+            GuardMode::Return => self.return_to_route(map, step),
+            GuardMode::Stunned { .. } => {}
+        }
+        alerted
+    }
+
+    // 抓到玩家后停下一会儿
+    pub fn stun(&mut self) {
+        self.mode = GuardMode::Stunned { remaining: STUN_DURATION };
+    }
+
+    // 与左上角在 (x, y)、边长为 size 的矩形是否重叠
+    pub fn touches(&self, x: f64, y: f64, size: f64) -> bool {
+        self.x < x + size && x < self.x + GUARD_SIZE && self.y < y + size && y < self.y + GUARD_SIZE
+    }
+
+    fn patrol(&mut self, map: &GameMap, step: f64) {
+        if self.route.len() < 2 {
+            return;
+        }
+        let target = tile_center(self.route[self.route_index], map.tile_size());
+        if self.move_towards(map, target, self.speed * step) {
+            // 走到路线的一端后掉头
+            if self.route_forward && self.route_index + 1 == self.route.len() {
+                self.route_forward = false;
+            } else if !self.route_forward && self.route_index == 0 {
+                self.route_forward = true;
+            }
+            if self.route_forward {
+                self.route_index += 1;
+            } else {
+                self.route_index -= 1;
+            }
+        }
+    }
+
+    // 求出从当前格子回到巡逻路线上下一个格子的路径
+    fn start_return(&mut self, map: &GameMap) {
+        let (x, y) = self.center();
+        let target = self.route[self.route_index];
+        self.path = pathfinding::find_path(map, map.tile_at(x, y), target, Connectivity::Eight).unwrap_or_default();
+        self.path.reverse();
+        self.mode = GuardMode::Return;
+    }
+
+    fn return_to_route(&mut self, map: &GameMap, step: f64) {
+        let Some(&tile) = self.path.last() else {
+            self.mode = GuardMode::Patrol;
+            return;
+        };
+        let (x, y) = (self.x, self.y);
+        let reached = self.move_towards(map, tile_center(tile, map.tile_size()), self.speed * step);
+        // 被卡住时跳过这个格子，不要一直停在原地
+        if reached || (self.x == x && self.y == y) {
+            self.path.pop();
+        }
+    }
+
+    // 沿墙滑动着向 target（守卫中心的目标位置）移动最多 distance 像素，返回是否已经到达
+    fn move_towards(&mut self, map: &GameMap, target: (f64, f64), distance: f64) -> bool {
+        let (x, y) = self.center();
+        let (dx, dy) = (target.0 - x, target.1 - y);
+        let length = dx.hypot(dy);
+        if length <= distance {
+            self.x = map.sweep_x(self.x, self.y, GUARD_SIZE, GUARD_SIZE, dx).0;
+            self.y = map.sweep_y(self.x, self.y, GUARD_SIZE, GUARD_SIZE, dy).0;
+            return true;
+        }
+        self.facing = (dx / length, dy / length);
+        let (move_x, move_y) = (self.facing.0 * distance, self.facing.1 * distance);
+        self.x = map.sweep_x(self.x, self.y, GUARD_SIZE, GUARD_SIZE, move_x).0;
+        self.y = map.sweep_y(self.x, self.y, GUARD_SIZE, GUARD_SIZE, move_y).0;
+        false
+    }
+}
+
+// 在能从出生点到达、离出生点足够远的空地上放置守卫，每个守卫的路线是一段随机游走
+pub fn spawn_guards(map: &GameMap, count: u32, spawn: (usize, usize), speed: f64, view_range: f64, rng: &mut Rng) -> Vec<Guard> {
+    let reachable = map.reachable_from(spawn.0, spawn.1);
+    let tiles: Vec<(usize, usize)> = (0..map.height())
+        .flat_map(|y| (0..map.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| reachable[y * map.width() + x] && (x, y) != spawn)
+        .collect();
+    let far: Vec<(usize, usize)> = tiles.iter().copied()
+        .filter(|&(x, y)| (x as f64 - spawn.0 as f64).hypot(y as f64 - spawn.1 as f64) >= MIN_SPAWN_DISTANCE)
+        .collect();
+    // 地图太小时放宽距离要求
+    let candidates = if far.is_empty() { tiles } else { far };
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+
+    (0..count)
+        .map(|_| {
+            let start = candidates[rng.range(0, candidates.len())];
+            Guard::new(random_route(map, start, rng), map.tile_size(), speed, view_range)
+        })
+        .collect()
+}
+
+// 从 start 出发随机游走，不重复经过同一个格子
+fn random_route(map: &GameMap, start: (usize, usize), rng: &mut Rng) -> Vec<(usize, usize)> {
+    let mut route = vec![start];
+    while route.len() < ROUTE_LENGTH {
+        let (x, y) = route[route.len() - 1];
+        let options: Vec<(usize, usize)> = [(x + 1, y), (x, y + 1), (x.wrapping_sub(1), y), (x, y.wrapping_sub(1))]
+            .into_iter()
+            .filter(|&(nx, ny)| map.is_tile_walkable(nx, ny) && !route.contains(&(nx, ny)))
+            .collect();
+        if options.is_empty() {
+            break;
+        }
+        route.push(options[rng.range(0, options.len())]);
+    }
+    route
+}
+
+fn tile_center(tile: (usize, usize), tile_size: f64) -> (f64, f64) {
+    ((tile.0 as f64 + 0.5) * tile_size, (tile.1 as f64 + 0.5) * tile_size)
+}
+
+// 守卫站在格子中心时的左上角坐标
+fn tile_origin(tile: (usize, usize), tile_size: f64) -> (f64, f64) {
+    let (x, y) = tile_center(tile, tile_size);
+    (x - GUARD_SIZE / 2.0, y - GUARD_SIZE / 2.0)
+}
@@ -0,0 +1,203 @@
+// src/leaderboard.rs
+// 排行榜：只保留分数最高的前 N 条记录，通过 Storage 读写，浏览器中存到 localStorage
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::difficulty::Difficulty;
+
+// 排行榜保留的记录数
+pub const LEADERBOARD_SIZE: usize = 10;
+
+// 当前排行榜存储格式版本，修改 LeaderboardEntry 时需要加一
+const LEADERBOARD_VERSION: u32 = 1;
+
+// 键值存储，浏览器中是 localStorage，原生环境可以用 MemoryStorage
+pub trait Storage {
+    fn get_item(&self, key: &str) -> Option<String>;
+    fn set_item(&mut self, key: &str, value: &str) -> Result<(), String>;
+}
+
+// 保存在内存中的存储，页面关闭后丢失
+#[derive(Default)]
+pub struct MemoryStorage {
+    items: HashMap<String, String>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get_item(&self, key: &str) -> Option<String> {
+        self.items.get(key).cloned()
+    }
+
+    fn set_item(&mut self, key: &str, value: &str) -> Result<(), String> {
+        self.items.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub score: u32,
+    // 用时（秒）
+    pub time: f64,
+    pub seed: u32,
+    pub difficulty: Difficulty,
+    // 记录时间（自 1970 年起的毫秒数，与 JS 的 Date.now() 相同）
+    pub date: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LeaderboardFile {
+    version: u32,
+    entries: Vec<LeaderboardEntry>,
+}
+
+pub struct Leaderboard {
+    entries: Vec<LeaderboardEntry>,
+    capacity: usize,
+}
+
+impl Leaderboard {
+    pub fn new(capacity: usize) -> Leaderboard {
+        Leaderboard { entries: Vec::new(), capacity }
+    }
+
+    // 从存储读取，数据缺失、损坏或版本不符时得到空的排行榜
+    pub fn load(storage: &dyn Storage, key: &str, capacity: usize) -> Leaderboard {
+        let mut leaderboard = Leaderboard::new(capacity);
+        let file = storage.get_item(key)
+            .and_then(|text| serde_json::from_str::<LeaderboardFile>(&text).ok())
+            .filter(|file| file.version == LEADERBOARD_VERSION);
+        if let Some(file) = file {
+            for entry in file.entries {
+                leaderboard.add(entry);
+            }
+        }
+        leaderboard
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage, key: &str) -> Result<(), String> {
+        storage.set_item(key, &self.to_json())
+    }
+
+    pub fn to_json(&self) -> String {
+        let file = LeaderboardFile { version: LEADERBOARD_VERSION, entries: self.entries.clone() };
+        serde_json::to_string(&file).expect("leaderboard is always serializable")
+    }
+
+    // 加入一条记录，返回名次（从 0 开始），没有进入前 N 名时返回 None
+    // 分数高的在前，分数相同时用时短的在前，再相同时先记录的在前
+    pub fn add(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+        if self.capacity == 0 || !entry.time.is_finite() {
+            return None;
+        }
+        let rank = self.entries.iter()
+            .position(|other| ranks_before(&entry, other))
+            .unwrap_or(self.entries.len());
+        if rank >= self.capacity {
+            return None;
+        }
+        self.entries.insert(rank, entry);
+        self.entries.truncate(self.capacity);
+        Some(rank)
+    }
+
+    pub fn entries(&self) -> &[LeaderboardEntry] {
+        &self.entries
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+fn ranks_before(entry: &LeaderboardEntry, other: &LeaderboardEntry) -> bool {
+    entry.score > other.score
+        || (entry.score == other.score && (entry.time < other.time || (entry.time == other.time && entry.date < other.date)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "leaderboard";
+
+    fn entry(name: &str, score: u32, time: f64, date: f64) -> LeaderboardEntry {
+        LeaderboardEntry {
+            name: name.to_string(),
+            score,
+            time,
+            seed: 1,
+            difficulty: Difficulty::default(),
+            date,
+        }
+    }
+
+    fn names(leaderboard: &Leaderboard) -> Vec<&str> {
+        leaderboard.entries().iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn keeps_only_the_top_n() {
+        let mut leaderboard = Leaderboard::new(3);
+        assert_eq!(leaderboard.add(entry("a", 10, 5.0, 0.0)), Some(0));
+        assert_eq!(leaderboard.add(entry("b", 30, 5.0, 1.0)), Some(0));
+        assert_eq!(leaderboard.add(entry("c", 20, 5.0, 2.0)), Some(1));
+        assert_eq!(leaderboard.add(entry("d", 40, 5.0, 3.0)), Some(0));
+        assert_eq!(leaderboard.add(entry("e", 5, 5.0, 4.0)), None);
+        assert_eq!(names(&leaderboard), vec!["d", "b", "c"]);
+    }
+
+    #[test]
+    fn ranks_ties_in_a_stable_order() {
+        let mut leaderboard = Leaderboard::new(LEADERBOARD_SIZE);
+        leaderboard.add(entry("slow", 10, 9.0, 0.0));
+        leaderboard.add(entry("fast", 10, 3.0, 1.0));
+        leaderboard.add(entry("later", 10, 3.0, 5.0));
+        leaderboard.add(entry("earlier", 10, 3.0, 2.0));
+        // 分数、用时和记录时间都相同时先加入的在前
+        assert_eq!(leaderboard.add(entry("same", 10, 3.0, 2.0)), Some(2));
+        assert_eq!(names(&leaderboard), vec!["fast", "earlier", "same", "later", "slow"]);
+    }
+
+    #[test]
+    fn survives_a_save_and_load() {
+        let mut storage = MemoryStorage::new();
+        let mut leaderboard = Leaderboard::new(LEADERBOARD_SIZE);
+        leaderboard.add(entry("a", 10, 5.0, 0.0));
+        leaderboard.add(entry("b", 20, 4.5, 1.0));
+        leaderboard.save(&mut storage, KEY).unwrap();
+
+        let loaded = Leaderboard::load(&storage, KEY, LEADERBOARD_SIZE);
+        assert_eq!(loaded.entries(), leaderboard.entries());
+
+        // 容量变小时只保留前面的记录
+        let smaller = Leaderboard::load(&storage, KEY, 1);
+        assert_eq!(names(&smaller), vec!["b"]);
+    }
+
+    #[test]
+    fn version_mismatch_gives_an_empty_board() {
+        let mut storage = MemoryStorage::new();
+        let mut leaderboard = Leaderboard::new(LEADERBOARD_SIZE);
+        leaderboard.add(entry("a", 10, 5.0, 0.0));
+        let json = leaderboard.to_json().replacen(
+            &format!("\"version\":{}", LEADERBOARD_VERSION),
+            &format!("\"version\":{}", LEADERBOARD_VERSION + 1),
+            1,
+        );
+        storage.set_item(KEY, &json).unwrap();
+        assert!(Leaderboard::load(&storage, KEY, LEADERBOARD_SIZE).entries().is_empty());
+
+        storage.set_item(KEY, "not json").unwrap();
+        assert!(Leaderboard::load(&storage, KEY, LEADERBOARD_SIZE).entries().is_empty());
+        assert!(Leaderboard::load(&storage, "missing", LEADERBOARD_SIZE).entries().is_empty());
+    }
+}
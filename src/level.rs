@@ -0,0 +1,221 @@
+// src/level.rs
+// 手工编辑的文本关卡格式，每个字符对应一个格子：
+//   # 墙   o 障碍物   . 空地   P 出生点   T 宝藏
+// 所有行必须等长，出生点有且只有一个，出生点和宝藏都不能放在最外圈
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::map::{GameMap, TileType};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Level {
+    pub map: GameMap,
+    pub spawn: (usize, usize),
+    pub treasures: Vec<(usize, usize)>,
+}
+
+// 关卡解析错误，行号和列号都从 1 开始
+#[derive(Clone, PartialEq, Debug)]
+pub enum LevelError {
+    Empty,
+    InvalidTile { line: usize, column: usize, found: char },
+    RaggedRow { line: usize, expected: usize, found: usize },
+    MissingSpawn,
+    DuplicateSpawn { line: usize, column: usize },
+    SpawnOutOfBounds { line: usize, column: usize },
+    SpawnBlocked { line: usize, column: usize },
+    TreasureOutOfBounds { line: usize, column: usize },
+    TreasureBlocked { line: usize, column: usize },
+    NoTreasures,
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelError::Empty => write!(f, "level is empty"),
+            LevelError::InvalidTile { line, column, found } => {
+                write!(f, "line {}, column {}: unknown tile '{}'", line, column, found)
+            }
+            LevelError::RaggedRow { line, expected, found } => {
+                write!(f, "line {}: expected {} columns, found {}", line, expected, found)
+            }
+            LevelError::MissingSpawn => write!(f, "level has no spawn point 'P'"),
+            LevelError::DuplicateSpawn { line, column } => {
+                write!(f, "line {}, column {}: more than one spawn point", line, column)
+            }
+            LevelError::SpawnOutOfBounds { line, column } => {
+                write!(f, "line {}, column {}: spawn point on the map border", line, column)
+            }
+            LevelError::SpawnBlocked { line, column } => {
+                write!(f, "line {}, column {}: spawn point is not on an empty tile", line, column)
+            }
+            LevelError::TreasureBlocked { line, column } => {
+                write!(f, "line {}, column {}: treasure is not on an empty tile", line, column)
+            }
+            LevelError::TreasureOutOfBounds { line, column } => {
+                write!(f, "line {}, column {}: treasure on the map border", line, column)
+            }
+            LevelError::NoTreasures => write!(f, "level has no treasures 'T'"),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl LevelError {
+    // 把格子坐标得到的行号往后移 offset 行
+    fn offset_lines(self, offset: usize) -> LevelError {
+        match self {
+            LevelError::SpawnOutOfBounds { line, column } => LevelError::SpawnOutOfBounds { line: line + offset, column },
+            LevelError::TreasureOutOfBounds { line, column } => LevelError::TreasureOutOfBounds { line: line + offset, column },
+            LevelError::SpawnBlocked { line, column } => LevelError::SpawnBlocked { line: line + offset, column },
+            LevelError::TreasureBlocked { line, column } => LevelError::TreasureBlocked { line: line + offset, column },
+            other => other,
+        }
+    }
+}
+
+impl Level {
+    pub fn parse(text: &str, tile_size: f64) -> Result<Level, LevelError> {
+        // 忽略开头和结尾的空行，但保留原始行号
+        let rows: Vec<(usize, &str)> = text
+            .lines()
+            .map(|line| line.trim_end())
+            .enumerate()
+            .map(|(index, line)| (index + 1, line))
+            .skip_while(|(_, line)| line.is_empty())
+            .collect();
+        let last = rows.iter().rposition(|(_, line)| !line.is_empty()).ok_or(LevelError::Empty)?;
+        let rows = &rows[..=last];
+
+        let width = rows[0].1.chars().count();
+        let height = rows.len();
+        let mut tiles = Vec::with_capacity(width * height);
+        let mut spawn = None;
+        let mut treasures = Vec::new();
+
+        for (y, &(line, row)) in rows.iter().enumerate() {
+            let found = row.chars().count();
+            if found != width {
+                return Err(LevelError::RaggedRow { line, expected: width, found });
+            }
+
+            for (x, ch) in row.chars().enumerate() {
+                let column = x + 1;
+                let tile = match ch {
+                    '#' => TileType::Wall,
+                    'o' => TileType::Obstacle,
+                    '.' => TileType::Empty,
+                    'P' => {
+                        if spawn.is_some() {
+                            return Err(LevelError::DuplicateSpawn { line, column });
+                        }
+                        spawn = Some((x, y));
+                        TileType::Empty
+                    }
+                    'T' => {
+                        treasures.push((x, y));
+                        TileType::Empty
+                    }
+                    _ => return Err(LevelError::InvalidTile { line, column, found: ch }),
+                };
+                tiles.push(tile);
+            }
+        }
+
+        let spawn = spawn.ok_or(LevelError::MissingSpawn)?;
+        let level = Level {
+            map: GameMap::from_tiles(width, height, tile_size, tiles),
+            spawn,
+            treasures,
+        };
+        // validate 从第 1 行开始数，这里要跳过开头的空行
+        level.validate().map_err(|error| error.offset_lines(rows[0].0 - 1))?;
+        Ok(level)
+    }
+
+    // 检查出生点和宝藏的位置：都要在地图内、不在最外圈并且是空地，至少要有一个宝藏
+    // 文本关卡、JSON 关卡文件和存档共用，行号和列号就是格子坐标加一
+    pub fn validate(&self) -> Result<(), LevelError> {
+        let (width, height) = (self.map.width(), self.map.height());
+        let (x, y) = self.spawn;
+        if is_border(x, y, width, height) {
+            return Err(LevelError::SpawnOutOfBounds { line: y + 1, column: x + 1 });
+        }
+        if self.map.get_tile(x, y) != TileType::Empty {
+            return Err(LevelError::SpawnBlocked { line: y + 1, column: x + 1 });
+        }
+        for &(x, y) in &self.treasures {
+            if is_border(x, y, width, height) {
+                return Err(LevelError::TreasureOutOfBounds { line: y + 1, column: x + 1 });
+            }
+            if self.map.get_tile(x, y) != TileType::Empty {
+                return Err(LevelError::TreasureBlocked { line: y + 1, column: x + 1 });
+            }
+        }
+        if self.treasures.is_empty() {
+            return Err(LevelError::NoTreasures);
+        }
+        Ok(())
+    }
+
+    pub fn to_level_string(&self) -> String {
+        let mut text = String::with_capacity((self.map.width() + 1) * self.map.height());
+        for y in 0..self.map.height() {
+            for x in 0..self.map.width() {
+                let ch = if (x, y) == self.spawn {
+                    'P'
+                } else if self.treasures.contains(&(x, y)) {
+                    'T'
+                } else {
+                    match self.map.get_tile(x, y) {
+                        TileType::Empty => '.',
+                        TileType::Wall => '#',
+                        TileType::Obstacle => 'o',
+                    }
+                };
+                text.push(ch);
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+// 最外圈或地图外的格子，玩家和宝藏放在这里会卡在墙里
+fn is_border(x: usize, y: usize, width: usize, height: usize) -> bool {
+    x == 0 || y == 0 || x + 1 >= width || y + 1 >= height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_round_trips() {
+        let text = "#####\n#P.T#\n#####\n";
+        let level = Level::parse(text, 40.0).unwrap();
+        assert_eq!(level.spawn, (1, 1));
+        assert_eq!(level.treasures, vec![(3, 1)]);
+        assert_eq!(level.to_level_string(), text);
+    }
+
+    #[test]
+    fn rejects_spawn_on_the_border() {
+        let error = Level::parse("#P###\n#..T#\n#####\n", 40.0).err();
+        assert_eq!(error, Some(LevelError::SpawnOutOfBounds { line: 1, column: 2 }));
+    }
+
+    #[test]
+    fn rejects_treasure_on_the_border() {
+        let error = Level::parse("#####\n#P..T\n#####\n", 40.0).err();
+        assert_eq!(error, Some(LevelError::TreasureOutOfBounds { line: 2, column: 5 }));
+    }
+
+    #[test]
+    fn reports_original_line_after_leading_blank_lines() {
+        let error = Level::parse("\n\n#####\n#P..T\n#####\n", 40.0).err();
+        assert_eq!(error, Some(LevelError::TreasureOutOfBounds { line: 4, column: 5 }));
+    }
+}
@@ -2,12 +2,35 @@
 use wasm_bindgen::prelude::*;
 mod game;
 mod renderer;
-mod player;
-mod map;
-mod treasure;
 mod audio;
+mod local_storage;
+
+// 平台无关的游戏核心，可在原生 Rust 中直接使用
+pub mod player;
+pub mod camera;
+pub mod campaign;
+pub mod cave;
+pub mod combo;
+pub mod config;
+pub mod difficulty;
+pub mod distance_field;
+pub mod dungeon;
+pub mod guard;
+pub mod leaderboard;
+pub mod level;
+pub mod map;
+pub mod maze;
+pub mod pathfinding;
+pub mod treasure;
+pub mod rng;
+pub mod save;
+pub mod simulation;
+pub mod state;
+pub mod tiled;
+
 pub use game::Game;
 pub use renderer::Renderer;
+pub use simulation::{GameEvent, Input, Simulation};
 
 #[wasm_bindgen(start)]
 pub fn start() {
@@ -0,0 +1,25 @@
+// src/local_storage.rs
+// 基于 window.localStorage 的 Storage，关闭页面后数据仍然保留
+use crate::leaderboard::Storage;
+
+pub struct LocalStorage {
+    storage: web_sys::Storage,
+}
+
+impl LocalStorage {
+    // 浏览器禁用 localStorage（例如某些隐私模式）时返回 None
+    pub fn new() -> Option<LocalStorage> {
+        let storage = web_sys::window()?.local_storage().ok()??;
+        Some(LocalStorage { storage })
+    }
+}
+
+impl Storage for LocalStorage {
+    fn get_item(&self, key: &str) -> Option<String> {
+        self.storage.get_item(key).ok().flatten()
+    }
+
+    fn set_item(&mut self, key: &str, value: &str) -> Result<(), String> {
+        self.storage.set_item(key, value).map_err(|e| format!("{:?}", e))
+    }
+}
@@ -1,37 +1,147 @@
-use wasm_bindgen::prelude::*;
-use js_sys::Math;
+use std::collections::VecDeque;
 
-#[wasm_bindgen]
-#[derive(Clone, Copy, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+use crate::cave::{self, CaveSettings};
+use crate::dungeon::{self, DungeonSettings};
+use crate::maze::{self, MazeAlgorithm};
+use crate::rng::Rng;
+
+// 判断矩形边缘所在格子时使用的微小偏移，避免刚好贴着格子边缘时算进下一格
+const COLLISION_EPSILON: f64 = 1e-6;
+
+// 阴影投射中八个卦限的坐标变换 (xx, xy, yx, yy)
+const OCTANTS: [(i64, i64, i64, i64); 8] = [
+    (1, 0, 0, 1),
+    (0, 1, 1, 0),
+    (0, -1, 1, 0),
+    (-1, 0, 0, 1),
+    (-1, 0, 0, -1),
+    (0, -1, -1, 0),
+    (0, 1, -1, 0),
+    (1, 0, 0, -1),
+];
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum TileType {
     Empty,
     Wall,
     Obstacle,
 }
 
-#[wasm_bindgen]
-#[derive(Clone)]
+// 地图生成方式
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub enum MapGenerator {
+    // 外墙加随机散布的障碍物
+    #[default]
+    Obstacles,
+    // 完美迷宫
+    Maze(MazeAlgorithm),
+    // 带环路的迷宫，loop_ratio 为被打通的死胡同比例（0 到 1）
+    BraidedMaze { algorithm: MazeAlgorithm, loop_ratio: f64 },
+    // 元胞自动机生成的洞穴
+    Cave(CaveSettings),
+    // 房间和走廊组成的地牢，部分房间是宝藏房间
+    Dungeon(DungeonSettings),
+}
+
+// 矩形房间（格子坐标）
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Room {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub is_treasure_room: bool,
+}
+
+impl Room {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Room {
+        Room { x, y, width, height, is_treasure_room: false }
+    }
+
+    pub fn center(&self) -> (usize, usize) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    // 两个房间（各自向外扩展 margin 格后）是否重叠
+    pub fn intersects(&self, other: &Room, margin: usize) -> bool {
+        self.x < other.x + other.width + margin
+            && other.x < self.x + self.width + margin
+            && self.y < other.y + other.height + margin
+            && other.y < self.y + self.height + margin
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GameMap {
     width: usize,
     height: usize,
     tile_size: f64,
     tiles: Vec<TileType>,
+    rooms: Vec<Room>,
+    // 战争迷雾：玩家见过的格子，为空表示还没有开始记录（例如关卡文件中的地图）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    explored: Vec<bool>,
+    // 玩家当前能看到的格子，每一步重新计算，不保存
+    #[serde(skip)]
+    visible: Vec<bool>,
+    // 空地总数和其中已探索的数量，与 explored 一起维护，读取存档后在 reveal_from 中重新统计
+    #[serde(skip)]
+    empty_tiles: usize,
+    #[serde(skip)]
+    explored_empty_tiles: usize,
 }
 
-#[wasm_bindgen]
 impl GameMap {
-    #[wasm_bindgen(constructor)]
-    pub fn new(width: usize, height: usize, tile_size: f64) -> GameMap {
+    // 地图布局完全由生成方式和传入的随机数生成器决定
+    // obstacle_density 是障碍物占地图的比例，只对 Obstacles 生效
+    pub fn new(width: usize, height: usize, tile_size: f64, generator: MapGenerator, obstacle_density: f64, rng: &mut Rng) -> GameMap {
         let mut map = GameMap {
             width,
             height,
             tile_size,
             tiles: vec![TileType::Empty; width * height],
+            rooms: Vec::new(),
+            explored: Vec::new(),
+            visible: Vec::new(),
+            empty_tiles: 0,
+            explored_empty_tiles: 0,
         };
-        map.generate_map();
+        match generator {
+            MapGenerator::Obstacles => map.generate_map(obstacle_density, rng),
+            MapGenerator::Maze(algorithm) => maze::generate(&mut map, algorithm, 0.0, rng),
+            MapGenerator::BraidedMaze { algorithm, loop_ratio } => {
+                maze::generate(&mut map, algorithm, loop_ratio, rng)
+            }
+            MapGenerator::Cave(settings) => cave::generate(&mut map, settings, rng),
+            MapGenerator::Dungeon(settings) => dungeon::generate(&mut map, settings, rng),
+        }
         map
     }
 
+    // 直接使用给定的格子创建地图，tiles 按行排列，长度必须是 width * height
+    pub fn from_tiles(width: usize, height: usize, tile_size: f64, tiles: Vec<TileType>) -> GameMap {
+        assert_eq!(tiles.len(), width * height, "tile count does not match map size");
+        GameMap {
+            width,
+            height,
+            tile_size,
+            tiles,
+            rooms: Vec::new(),
+            explored: Vec::new(),
+            visible: Vec::new(),
+            empty_tiles: 0,
+            explored_empty_tiles: 0,
+        }
+    }
+
+    // 格子数量是否与宽高一致（用于检查反序列化得到的地图）
+    pub fn is_consistent(&self) -> bool {
+        self.tiles.len() == self.width * self.height
+            && (self.explored.is_empty() || self.explored.len() == self.tiles.len())
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
@@ -52,7 +162,32 @@ impl GameMap {
         }
     }
 
-    fn generate_map(&mut self) {
+    pub fn set_tile(&mut self, x: usize, y: usize, tile: TileType) {
+        if x < self.width && y < self.height {
+            let index = y * self.width + x;
+            if self.explored.len() == self.tiles.len() {
+                self.uncount_tile(index);
+                self.tiles[index] = tile;
+                self.count_tile(index);
+            } else {
+                self.tiles[index] = tile;
+            }
+        }
+    }
+
+    pub fn rooms(&self) -> &[Room] {
+        &self.rooms
+    }
+
+    pub fn set_rooms(&mut self, rooms: Vec<Room>) {
+        self.rooms = rooms;
+    }
+
+    pub fn treasure_rooms(&self) -> impl Iterator<Item = &Room> {
+        self.rooms.iter().filter(|room| room.is_treasure_room)
+    }
+
+    fn generate_map(&mut self, obstacle_density: f64, rng: &mut Rng) {
         // 生成外墙
         for x in 0..self.width {
             self.tiles[x] = TileType::Wall; // 上墙
@@ -70,14 +205,14 @@ impl GameMap {
         ];
 
         // 随机生成障碍物，但避开左上角
-        let obstacle_count = (self.width * self.height) as f64 * 0.1; // 10% 的区域是障碍物
+        let obstacle_count = (self.width * self.height) as f64 * obstacle_density;
         let mut placed = 0;
         let max_attempts = (obstacle_count as i32) * 100;
         let mut attempts = 0;
 
         while placed < obstacle_count as i32 && attempts < max_attempts {
-            let x = (Math::random() * (self.width - 2) as f64) as usize + 1;
-            let y = (Math::random() * (self.height - 2) as f64) as usize + 1;
+            let x = rng.range(1, self.width - 1);
+            let y = rng.range(1, self.height - 1);
 
             // 检查是否在保护区域内
             let is_protected = protected_area.iter()
@@ -91,9 +226,454 @@ impl GameMap {
         }
     }
 
+    // 所有格子回到未探索、不可见的状态
+    pub fn reset_visibility(&mut self) {
+        self.explored = vec![false; self.tiles.len()];
+        self.visible = vec![false; self.tiles.len()];
+        self.recount_explored();
+    }
+
+    // 不再记录探索进度（导出关卡时使用）
+    pub fn clear_visibility(&mut self) {
+        self.explored.clear();
+        self.visible.clear();
+        self.empty_tiles = 0;
+        self.explored_empty_tiles = 0;
+    }
+
+    fn recount_explored(&mut self) {
+        self.empty_tiles = 0;
+        self.explored_empty_tiles = 0;
+        for index in 0..self.tiles.len() {
+            self.count_tile(index);
+        }
+    }
+
+    fn count_tile(&mut self, index: usize) {
+        if self.tiles[index] == TileType::Empty {
+            self.empty_tiles += 1;
+            if self.explored[index] {
+                self.explored_empty_tiles += 1;
+            }
+        }
+    }
+
+    fn uncount_tile(&mut self, index: usize) {
+        if self.tiles[index] == TileType::Empty {
+            self.empty_tiles -= 1;
+            if self.explored[index] {
+                self.explored_empty_tiles -= 1;
+            }
+        }
+    }
+
+    // 重新计算从格子 (x, y) 出发、半径 radius 格以内能看到的格子（递归阴影投射，
+    // 墙和障碍物挡住后面的视线，但它们本身可见），看到的格子记为已探索
+    pub fn reveal_from(&mut self, x: usize, y: usize, radius: f64) {
+        if self.explored.len() != self.tiles.len() {
+            self.reset_visibility();
+        } else if self.visible.len() != self.tiles.len() {
+            // 从存档读取的地图只有探索记录
+            self.visible = vec![false; self.tiles.len()];
+            self.recount_explored();
+        } else {
+            self.visible.fill(false);
+        }
+        self.mark_visible(x as i64, y as i64);
+        let range = radius.max(0.0).ceil() as i64;
+        for octant in OCTANTS {
+            self.cast_light((x as i64, y as i64), 1, 1.0, 0.0, range, radius, octant);
+        }
+    }
+
+    // 在一个卦限内逐行扫描，slope 从 start 递减到 end 的扇形区域没有被挡住
+    #[allow(clippy::too_many_arguments)]
+    fn cast_light(&mut self, origin: (i64, i64), row: i64, mut start: f64, end: f64, range: i64, radius: f64, octant: (i64, i64, i64, i64)) {
+        if start < end {
+            return;
+        }
+        let (xx, xy, yx, yy) = octant;
+        let mut next_start = start;
+        for distance in row..=range {
+            let mut blocked = false;
+            let dy = -distance;
+            for dx in -distance..=0 {
+                let x = origin.0 + dx * xx + dy * xy;
+                let y = origin.1 + dx * yx + dy * yy;
+                let left_slope = (dx as f64 - 0.5) / (dy as f64 + 0.5);
+                let right_slope = (dx as f64 + 0.5) / (dy as f64 - 0.5);
+                if start < right_slope {
+                    continue;
+                }
+                if end > left_slope {
+                    break;
+                }
+
+                if ((dx * dx + dy * dy) as f64) <= radius * radius {
+                    self.mark_visible(x, y);
+                }
+                let opaque = self.is_solid(x, y);
+                if blocked {
+                    if opaque {
+                        next_start = right_slope;
+                    } else {
+                        blocked = false;
+                        start = next_start;
+                    }
+                } else if opaque && distance < range {
+                    // 被挡住的部分之前的扇形继续向外扫描
+                    blocked = true;
+                    self.cast_light(origin, distance + 1, start, left_slope, range, radius, octant);
+                    next_start = right_slope;
+                }
+            }
+            if blocked {
+                break;
+            }
+        }
+    }
+
+    fn mark_visible(&mut self, x: i64, y: i64) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            let index = y as usize * self.width + x as usize;
+            self.visible[index] = true;
+            if !self.explored[index] {
+                self.explored[index] = true;
+                if self.tiles[index] == TileType::Empty {
+                    self.explored_empty_tiles += 1;
+                }
+            }
+        }
+    }
+
+    pub fn is_explored(&self, x: usize, y: usize) -> bool {
+        x < self.width && self.explored.get(y * self.width + x).copied().unwrap_or(false)
+    }
+
+    pub fn is_visible(&self, x: usize, y: usize) -> bool {
+        x < self.width && self.visible.get(y * self.width + x).copied().unwrap_or(false)
+    }
+
+    // 已探索的空地占全部空地的比例（0 到 1）
+    pub fn exploration(&self) -> f64 {
+        if self.empty_tiles == 0 {
+            return 0.0;
+        }
+        self.explored_empty_tiles as f64 / self.empty_tiles as f64
+    }
+
     pub fn is_walkable(&self, x: f64, y: f64) -> bool {
         let tile_x = (x / self.tile_size) as usize;
         let tile_y = (y / self.tile_size) as usize;
         self.get_tile(tile_x, tile_y) == TileType::Empty
     }
-}
\ No newline at end of file
+
+    // 沿 X 轴移动一个轴对齐的矩形（左上角 x, y，宽 width，高 height），
+    // 逐列检查经过的格子，碰到不可行走的格子时贴着格子边缘停下。
+    // 返回新的 x 和是否发生了碰撞
+    pub fn sweep_x(&self, x: f64, y: f64, width: f64, height: f64, dx: f64) -> (f64, bool) {
+        if dx == 0.0 {
+            return (x, false);
+        }
+        let (top, bottom) = self.span(y, height);
+        let target = x + dx;
+
+        if dx > 0.0 {
+            let start = self.tile_index(x + width - COLLISION_EPSILON) + 1;
+            let end = self.tile_index(target + width - COLLISION_EPSILON);
+            for column in start..=end {
+                if (top..=bottom).any(|row| self.is_solid(column, row)) {
+                    return (column as f64 * self.tile_size - width, true);
+                }
+            }
+        } else {
+            let start = self.tile_index(x) - 1;
+            let end = self.tile_index(target);
+            for column in (end..=start).rev() {
+                if (top..=bottom).any(|row| self.is_solid(column, row)) {
+                    return ((column + 1) as f64 * self.tile_size, true);
+                }
+            }
+        }
+        (target, false)
+    }
+
+    // 与 sweep_x 相同，沿 Y 轴移动
+    pub fn sweep_y(&self, x: f64, y: f64, width: f64, height: f64, dy: f64) -> (f64, bool) {
+        if dy == 0.0 {
+            return (y, false);
+        }
+        let (left, right) = self.span(x, width);
+        let target = y + dy;
+
+        if dy > 0.0 {
+            let start = self.tile_index(y + height - COLLISION_EPSILON) + 1;
+            let end = self.tile_index(target + height - COLLISION_EPSILON);
+            for row in start..=end {
+                if (left..=right).any(|column| self.is_solid(column, row)) {
+                    return (row as f64 * self.tile_size - height, true);
+                }
+            }
+        } else {
+            let start = self.tile_index(y) - 1;
+            let end = self.tile_index(target);
+            for row in (end..=start).rev() {
+                if (left..=right).any(|column| self.is_solid(column, row)) {
+                    return ((row + 1) as f64 * self.tile_size, true);
+                }
+            }
+        }
+        (target, false)
+    }
+
+    // 两点（像素坐标）之间的视线是否畅通：沿线段逐格前进，经过墙或障碍物时被挡住
+    pub fn has_line_of_sight(&self, from: (f64, f64), to: (f64, f64)) -> bool {
+        let (mut x, mut y) = (self.tile_index(from.0), self.tile_index(from.1));
+        let (end_x, end_y) = (self.tile_index(to.0), self.tile_index(to.1));
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let (step_x, step_y) = (if dx > 0.0 { 1 } else { -1 }, if dy > 0.0 { 1 } else { -1 });
+
+        // 沿线段走到下一条竖直（水平）格线时的进度 t，以及跨过一整格所需的 t
+        let boundary = |tile: i64, step: i64| if step > 0 { (tile + 1) as f64 } else { tile as f64 } * self.tile_size;
+        let mut t_max_x = if dx != 0.0 { (boundary(x, step_x) - from.0) / dx } else { f64::INFINITY };
+        let mut t_max_y = if dy != 0.0 { (boundary(y, step_y) - from.1) / dy } else { f64::INFINITY };
+        let t_delta_x = if dx != 0.0 { self.tile_size / dx.abs() } else { f64::INFINITY };
+        let t_delta_y = if dy != 0.0 { self.tile_size / dy.abs() } else { f64::INFINITY };
+
+        let steps = (end_x - x).abs() + (end_y - y).abs();
+        for _ in 0..steps {
+            if self.is_solid(x, y) {
+                return false;
+            }
+            if t_max_x < t_max_y {
+                x += step_x;
+                t_max_x += t_delta_x;
+            } else {
+                y += step_y;
+                t_max_y += t_delta_y;
+            }
+        }
+        !self.is_solid(x, y)
+    }
+
+    // 像素坐标对应的格子编号（可以为负数）
+    fn tile_index(&self, position: f64) -> i64 {
+        (position / self.tile_size).floor() as i64
+    }
+
+    // 从 start 开始长度为 length 的区间覆盖的格子范围（包含两端）
+    fn span(&self, start: f64, length: f64) -> (i64, i64) {
+        (self.tile_index(start), self.tile_index(start + length - COLLISION_EPSILON))
+    }
+
+    // 地图外的格子视为墙
+    fn is_solid(&self, x: i64, y: i64) -> bool {
+        x < 0 || y < 0 || !self.is_tile_walkable(x as usize, y as usize)
+    }
+
+    pub fn is_tile_walkable(&self, x: usize, y: usize) -> bool {
+        self.get_tile(x, y) == TileType::Empty
+    }
+
+    // 像素坐标所在的格子
+    pub fn tile_at(&self, x: f64, y: f64) -> (usize, usize) {
+        ((x / self.tile_size) as usize, (y / self.tile_size) as usize)
+    }
+
+    // 从起点格子做洪水填充（四连通），返回每个格子是否可以到达
+    pub fn reachable_from(&self, start_x: usize, start_y: usize) -> Vec<bool> {
+        let mut reachable = vec![false; self.width * self.height];
+        if !self.is_tile_walkable(start_x, start_y) {
+            return reachable;
+        }
+
+        let mut queue = VecDeque::new();
+        reachable[start_y * self.width + start_x] = true;
+        queue.push_back((start_x, start_y));
+
+        while let Some((x, y)) = queue.pop_front() {
+            for (nx, ny) in self.neighbors(x, y) {
+                let index = ny * self.width + nx;
+                if !reachable[index] && self.is_tile_walkable(nx, ny) {
+                    reachable[index] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        reachable
+    }
+
+    // 打通被障碍物隔开的空地，使所有空地都能从起点到达
+    // 返回被清除的格子数量
+    pub fn connect_regions(&mut self, start_x: usize, start_y: usize) -> usize {
+        let mut carved = 0;
+        loop {
+            let reachable = self.reachable_from(start_x, start_y);
+            let isolated = (0..self.tiles.len())
+                .find(|&i| self.tiles[i] == TileType::Empty && !reachable[i]);
+
+            let Some(index) = isolated else {
+                return carved;
+            };
+
+            // 从孤立的空地出发，穿过内部障碍寻找最近的可到达格子，然后沿路径清除障碍
+            let path = self.path_to_reachable(index % self.width, index / self.width, &reachable);
+            if path.is_empty() {
+                return carved;
+            }
+            for (x, y) in path {
+                let tile = &mut self.tiles[y * self.width + x];
+                if *tile != TileType::Empty {
+                    *tile = TileType::Empty;
+                    carved += 1;
+                }
+            }
+        }
+    }
+
+    // 广度优先搜索，可以穿过除外墙以外的任何格子
+    fn path_to_reachable(&self, start_x: usize, start_y: usize, reachable: &[bool]) -> Vec<(usize, usize)> {
+        let mut came_from: Vec<Option<usize>> = vec![None; self.width * self.height];
+        let mut visited = vec![false; self.width * self.height];
+        let mut queue = VecDeque::new();
+        visited[start_y * self.width + start_x] = true;
+        queue.push_back((start_x, start_y));
+
+        while let Some((x, y)) = queue.pop_front() {
+            if reachable[y * self.width + x] {
+                let mut path = Vec::new();
+                let mut current = Some(y * self.width + x);
+                while let Some(index) = current {
+                    path.push((index % self.width, index / self.width));
+                    current = came_from[index];
+                }
+                return path;
+            }
+
+            for (nx, ny) in self.neighbors(x, y) {
+                let index = ny * self.width + nx;
+                if !visited[index] && !self.is_border(nx, ny) {
+                    visited[index] = true;
+                    came_from[index] = Some(y * self.width + x);
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        Vec::new()
+    }
+
+    fn is_border(&self, x: usize, y: usize) -> bool {
+        x == 0 || y == 0 || x + 1 >= self.width || y + 1 >= self.height
+    }
+
+    // 上下左右四个相邻格子（不超出地图范围）
+    fn neighbors(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width, self.height);
+        [(0, -1), (0, 1), (-1, 0), (1, 0)]
+            .into_iter()
+            .filter_map(move |(dx, dy): (i32, i32)| {
+                let nx = x as i32 + dx;
+                let ny = y as i32 + dy;
+                if nx >= 0 && ny >= 0 && (nx as usize) < width && (ny as usize) < height {
+                    Some((nx as usize, ny as usize))
+                } else {
+                    None
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 中间一堵墙把地图分成左右两半
+    fn split_room() -> GameMap {
+        let level = crate::level::Level::parse(
+            "#########\n#P..#..T#\n#...#...#\n#########\n",
+            40.0,
+        ).unwrap();
+        level.map
+    }
+
+    fn count_explored_empty(map: &GameMap) -> usize {
+        (0..map.height())
+            .flat_map(|y| (0..map.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| map.get_tile(x, y) == TileType::Empty && map.is_explored(x, y))
+            .count()
+    }
+
+    #[test]
+    fn walls_block_the_view() {
+        let mut map = split_room();
+        map.reveal_from(1, 1, 10.0);
+        assert!(map.is_visible(3, 2));
+        assert!(map.is_visible(4, 1));
+        assert!(!map.is_visible(6, 1));
+        assert!(!map.is_explored(7, 2));
+        assert_eq!(map.exploration(), 6.0 / 12.0);
+    }
+
+    #[test]
+    fn explored_tiles_stay_explored_and_visible_ones_move() {
+        let mut map = split_room();
+        map.reveal_from(1, 1, 1.0);
+        let first = count_explored_empty(&map);
+        map.reveal_from(3, 2, 1.0);
+        assert!(map.is_explored(1, 1));
+        assert!(!map.is_visible(1, 1));
+        assert!(count_explored_empty(&map) > first);
+        assert_eq!(map.exploration(), count_explored_empty(&map) as f64 / 12.0);
+    }
+
+    #[test]
+    fn exploration_survives_a_save_round_trip() {
+        let mut map = split_room();
+        map.reveal_from(1, 1, 10.0);
+        let mut loaded: GameMap = serde_json::from_str(&serde_json::to_string(&map).unwrap()).unwrap();
+        loaded.reveal_from(1, 1, 10.0);
+        assert_eq!(loaded.exploration(), map.exploration());
+        assert!(loaded.is_visible(1, 1));
+    }
+
+    #[test]
+    fn set_tile_keeps_the_explored_count() {
+        let mut map = split_room();
+        map.reveal_from(1, 1, 10.0);
+        map.set_tile(2, 2, TileType::Obstacle);
+        assert_eq!(map.exploration(), 5.0 / 11.0);
+        map.set_tile(4, 1, TileType::Empty);
+        assert_eq!(map.exploration(), 6.0 / 12.0);
+    }
+
+    #[test]
+    fn sweep_stops_flush_against_a_tile_edge() {
+        let map = split_room();
+        // 右边的墙在第 4 列，左边缘 x = 160
+        assert_eq!(map.sweep_x(130.0, 50.0, 20.0, 20.0, 15.0), (140.0, true));
+        assert_eq!(map.sweep_x(130.0, 50.0, 20.0, 20.0, 10.0), (140.0, false));
+        assert_eq!(map.sweep_x(50.0, 50.0, 20.0, 20.0, -30.0), (40.0, true));
+        assert_eq!(map.sweep_y(50.0, 90.0, 20.0, 20.0, 40.0), (100.0, true));
+        assert_eq!(map.sweep_y(50.0, 45.0, 20.0, 20.0, -10.0), (40.0, true));
+    }
+
+    #[test]
+    fn long_moves_do_not_pass_through_walls() {
+        let map = split_room();
+        assert_eq!(map.sweep_x(50.0, 50.0, 20.0, 20.0, 1000.0), (140.0, true));
+        assert_eq!(map.sweep_x(250.0, 50.0, 20.0, 20.0, -1000.0), (200.0, true));
+        assert_eq!(map.sweep_y(50.0, 50.0, 20.0, 20.0, 1000.0), (100.0, true));
+    }
+
+    #[test]
+    fn diagonal_move_slides_along_a_wall() {
+        let map = split_room();
+        let mut player = crate::player::Player::new(60.0, 45.0);
+        player.set_velocity(200.0, -200.0);
+        player.move_and_slide(&map, 0.1);
+        // 碰到上面的墙后停在墙边，水平方向继续移动
+        assert_eq!((player.x(), player.y()), (80.0, 40.0));
+        assert_eq!(player.velocity().x, 200.0);
+        assert_eq!(player.velocity().y, 0.0);
+    }
+}
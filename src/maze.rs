@@ -0,0 +1,220 @@
+// src/maze.rs
+// 迷宫生成：奇数坐标的格子是房间，房间之间的格子是可以打通的墙
+use serde::{Deserialize, Serialize};
+
+use crate::map::{GameMap, TileType};
+use crate::rng::Rng;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum MazeAlgorithm {
+    RecursiveBacktracker,
+    Prim,
+    Kruskal,
+}
+
+impl MazeAlgorithm {
+    pub fn from_name(name: &str) -> Option<MazeAlgorithm> {
+        match name {
+            "backtracker" => Some(MazeAlgorithm::RecursiveBacktracker),
+            "prim" => Some(MazeAlgorithm::Prim),
+            "kruskal" => Some(MazeAlgorithm::Kruskal),
+            _ => None,
+        }
+    }
+}
+
+// 在地图上生成迷宫。loop_ratio 为 0 时是完美迷宫（任意两点只有一条路），
+// 大于 0 时按比例打通死胡同形成环路（braided maze）
+pub fn generate(map: &mut GameMap, algorithm: MazeAlgorithm, loop_ratio: f64, rng: &mut Rng) {
+    let cells_x = map.width().saturating_sub(1) / 2;
+    let cells_y = map.height().saturating_sub(1) / 2;
+    if cells_x == 0 || cells_y == 0 {
+        return;
+    }
+
+    // 先全部填满墙，再打通房间
+    for y in 0..map.height() {
+        for x in 0..map.width() {
+            map.set_tile(x, y, TileType::Wall);
+        }
+    }
+    for cy in 0..cells_y {
+        for cx in 0..cells_x {
+            let (x, y) = cell_to_tile(cx, cy);
+            map.set_tile(x, y, TileType::Empty);
+        }
+    }
+
+    let grid = CellGrid { width: cells_x, height: cells_y };
+    match algorithm {
+        MazeAlgorithm::RecursiveBacktracker => recursive_backtracker(map, &grid, rng),
+        MazeAlgorithm::Prim => prim(map, &grid, rng),
+        MazeAlgorithm::Kruskal => kruskal(map, &grid, rng),
+    }
+
+    if loop_ratio > 0.0 {
+        braid(map, &grid, loop_ratio.min(1.0), rng);
+    }
+}
+
+struct CellGrid {
+    width: usize,
+    height: usize,
+}
+
+impl CellGrid {
+    fn index(&self, cx: usize, cy: usize) -> usize {
+        cy * self.width + cx
+    }
+
+    fn neighbors(&self, cx: usize, cy: usize) -> Vec<(usize, usize)> {
+        let mut result = Vec::with_capacity(4);
+        if cy > 0 {
+            result.push((cx, cy - 1));
+        }
+        if cy + 1 < self.height {
+            result.push((cx, cy + 1));
+        }
+        if cx > 0 {
+            result.push((cx - 1, cy));
+        }
+        if cx + 1 < self.width {
+            result.push((cx + 1, cy));
+        }
+        result
+    }
+}
+
+fn cell_to_tile(cx: usize, cy: usize) -> (usize, usize) {
+    (cx * 2 + 1, cy * 2 + 1)
+}
+
+// 打通两个相邻房间之间的墙
+fn open_passage(map: &mut GameMap, a: (usize, usize), b: (usize, usize)) {
+    let (ax, ay) = cell_to_tile(a.0, a.1);
+    let (bx, by) = cell_to_tile(b.0, b.1);
+    map.set_tile((ax + bx) / 2, (ay + by) / 2, TileType::Empty);
+}
+
+fn is_passage_open(map: &GameMap, a: (usize, usize), b: (usize, usize)) -> bool {
+    let (ax, ay) = cell_to_tile(a.0, a.1);
+    let (bx, by) = cell_to_tile(b.0, b.1);
+    map.get_tile((ax + bx) / 2, (ay + by) / 2) == TileType::Empty
+}
+
+// 深度优先的递归回溯（用显式栈避免递归过深）
+fn recursive_backtracker(map: &mut GameMap, grid: &CellGrid, rng: &mut Rng) {
+    let mut visited = vec![false; grid.width * grid.height];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+
+    while let Some(&(cx, cy)) = stack.last() {
+        let unvisited: Vec<(usize, usize)> = grid.neighbors(cx, cy)
+            .into_iter()
+            .filter(|&(nx, ny)| !visited[grid.index(nx, ny)])
+            .collect();
+
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let next = unvisited[rng.range(0, unvisited.len())];
+        visited[grid.index(next.0, next.1)] = true;
+        open_passage(map, (cx, cy), next);
+        stack.push(next);
+    }
+}
+
+// 随机化 Prim：从已加入迷宫的区域边界上随机挑选房间加入
+fn prim(map: &mut GameMap, grid: &CellGrid, rng: &mut Rng) {
+    let mut in_maze = vec![false; grid.width * grid.height];
+    let mut frontier: Vec<((usize, usize), (usize, usize))> = Vec::new();
+
+    in_maze[0] = true;
+    for neighbor in grid.neighbors(0, 0) {
+        frontier.push(((0, 0), neighbor));
+    }
+
+    while !frontier.is_empty() {
+        let (from, to) = frontier.swap_remove(rng.range(0, frontier.len()));
+        if in_maze[grid.index(to.0, to.1)] {
+            continue;
+        }
+
+        in_maze[grid.index(to.0, to.1)] = true;
+        open_passage(map, from, to);
+        for neighbor in grid.neighbors(to.0, to.1) {
+            if !in_maze[grid.index(neighbor.0, neighbor.1)] {
+                frontier.push((to, neighbor));
+            }
+        }
+    }
+}
+
+// 随机化 Kruskal：打乱所有墙，用并查集只打通连接不同集合的墙
+fn kruskal(map: &mut GameMap, grid: &CellGrid, rng: &mut Rng) {
+    let mut edges = Vec::new();
+    for cy in 0..grid.height {
+        for cx in 0..grid.width {
+            if cx + 1 < grid.width {
+                edges.push(((cx, cy), (cx + 1, cy)));
+            }
+            if cy + 1 < grid.height {
+                edges.push(((cx, cy), (cx, cy + 1)));
+            }
+        }
+    }
+    rng.shuffle(&mut edges);
+
+    let mut parent: Vec<usize> = (0..grid.width * grid.height).collect();
+    for (a, b) in edges {
+        let root_a = find_root(&mut parent, grid.index(a.0, a.1));
+        let root_b = find_root(&mut parent, grid.index(b.0, b.1));
+        if root_a != root_b {
+            parent[root_a] = root_b;
+            open_passage(map, a, b);
+        }
+    }
+}
+
+fn find_root(parent: &mut [usize], mut index: usize) -> usize {
+    while parent[index] != index {
+        parent[index] = parent[parent[index]];
+        index = parent[index];
+    }
+    index
+}
+
+// 按 loop_ratio 的比例消除死胡同，优先连向另一个死胡同
+fn braid(map: &mut GameMap, grid: &CellGrid, loop_ratio: f64, rng: &mut Rng) {
+    let mut cells: Vec<(usize, usize)> = (0..grid.height)
+        .flat_map(|cy| (0..grid.width).map(move |cx| (cx, cy)))
+        .collect();
+    rng.shuffle(&mut cells);
+
+    for cell in cells {
+        let neighbors = grid.neighbors(cell.0, cell.1);
+        let open_count = neighbors.iter().filter(|&&n| is_passage_open(map, cell, n)).count();
+        if open_count != 1 || !rng.chance(loop_ratio) {
+            continue;
+        }
+
+        let closed: Vec<(usize, usize)> = neighbors.into_iter()
+            .filter(|&n| !is_passage_open(map, cell, n))
+            .collect();
+        if closed.is_empty() {
+            continue;
+        }
+
+        let dead_ends: Vec<(usize, usize)> = closed.iter()
+            .copied()
+            .filter(|&n| {
+                grid.neighbors(n.0, n.1).iter().filter(|&&m| is_passage_open(map, n, m)).count() == 1
+            })
+            .collect();
+        let candidates = if dead_ends.is_empty() { &closed } else { &dead_ends };
+        let target = candidates[rng.range(0, candidates.len())];
+        open_passage(map, cell, target);
+    }
+}
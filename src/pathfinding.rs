@@ -0,0 +1,213 @@
+// src/pathfinding.rs
+// A* 寻路：在 GameMap 的空地上求两个格子之间的最短路径
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::map::GameMap;
+
+const DIAGONAL_COST: f64 = std::f64::consts::SQRT_2;
+
+// 每一步可以走向哪些相邻格子
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Connectivity {
+    // 上下左右
+    #[default]
+    Four,
+    // 再加上四个斜向，斜着走时两侧的格子都必须是空地，不能贴着障碍物的角穿过去
+    Eight,
+}
+
+// 优先队列中的节点，按 f 从小到大弹出（A* 中 f = g + h，Dijkstra 中 f 就是距离）
+pub(crate) struct Node {
+    pub(crate) f: f64,
+    pub(crate) index: usize,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.f == other.f
+    }
+}
+
+impl Eq for Node {}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap 是最大堆，反过来比较得到最小的 f
+        other.f.total_cmp(&self.f).then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// 返回从 from 到 to 的格子路径（包含两端），起点或终点不是空地、或者走不到时返回 None
+pub fn find_path(map: &GameMap, from: (usize, usize), to: (usize, usize), connectivity: Connectivity) -> Option<Vec<(usize, usize)>> {
+    if !map.is_tile_walkable(from.0, from.1) || !map.is_tile_walkable(to.0, to.1) {
+        return None;
+    }
+
+    let width = map.width();
+    let start = from.1 * width + from.0;
+    let goal = to.1 * width + to.0;
+    let mut cost = vec![f64::INFINITY; width * map.height()];
+    let mut came_from: Vec<Option<usize>> = vec![None; width * map.height()];
+    let mut open = BinaryHeap::new();
+    cost[start] = 0.0;
+    open.push(Node { f: heuristic(from, to, connectivity), index: start });
+
+    while let Some(Node { f, index }) = open.pop() {
+        if index == goal {
+            return Some(reconstruct(&came_from, goal, width));
+        }
+        let tile = (index % width, index / width);
+        // 已经有更短的路径到达这个格子，跳过过期的节点
+        if f > cost[index] + heuristic(tile, to, connectivity) {
+            continue;
+        }
+
+        for (neighbor, step_cost) in neighbors(map, tile, connectivity) {
+            let next = neighbor.1 * width + neighbor.0;
+            let next_cost = cost[index] + step_cost;
+            if next_cost < cost[next] {
+                cost[next] = next_cost;
+                came_from[next] = Some(index);
+                open.push(Node { f: next_cost + heuristic(neighbor, to, connectivity), index: next });
+            }
+        }
+    }
+    None
+}
+
+// 曼哈顿距离（四连通）或八方向距离（八连通），都不会高估实际距离
+fn heuristic(a: (usize, usize), b: (usize, usize), connectivity: Connectivity) -> f64 {
+    let dx = a.0.abs_diff(b.0) as f64;
+    let dy = a.1.abs_diff(b.1) as f64;
+    match connectivity {
+        Connectivity::Four => dx + dy,
+        Connectivity::Eight => dx.max(dy) + (DIAGONAL_COST - 1.0) * dx.min(dy),
+    }
+}
+
+// 可以走到的相邻空地及其代价
+pub(crate) fn neighbors(map: &GameMap, (x, y): (usize, usize), connectivity: Connectivity) -> Vec<((usize, usize), f64)> {
+    let walkable = |dx: i64, dy: i64| {
+        let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+        nx >= 0 && ny >= 0 && map.is_tile_walkable(nx as usize, ny as usize)
+    };
+    let offset = |dx: i64, dy: i64| ((x as i64 + dx) as usize, (y as i64 + dy) as usize);
+
+    let mut result = Vec::with_capacity(8);
+    for (dx, dy) in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
+        if walkable(dx, dy) {
+            result.push((offset(dx, dy), 1.0));
+        }
+    }
+    if connectivity == Connectivity::Eight {
+        for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            if walkable(dx, dy) && walkable(dx, 0) && walkable(0, dy) {
+                result.push((offset(dx, dy), DIAGONAL_COST));
+            }
+        }
+    }
+    result
+}
+
+fn reconstruct(came_from: &[Option<usize>], goal: usize, width: usize) -> Vec<(usize, usize)> {
+    let mut path = Vec::new();
+    let mut current = Some(goal);
+    while let Some(index) = current {
+        path.push((index % width, index / width));
+        current = came_from[index];
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TileType;
+
+    fn map(rows: &[&str]) -> GameMap {
+        let tiles = rows.iter()
+            .flat_map(|row| row.chars())
+            .map(|ch| match ch {
+                '#' => TileType::Wall,
+                'o' => TileType::Obstacle,
+                _ => TileType::Empty,
+            })
+            .collect();
+        GameMap::from_tiles(rows[0].len(), rows.len(), 40.0, tiles)
+    }
+
+    // 路径的总代价，同时检查每一步都是相邻的空地
+    fn path_cost(map: &GameMap, path: &[(usize, usize)]) -> f64 {
+        path.windows(2)
+            .map(|step| {
+                let (a, b) = (step[0], step[1]);
+                assert!(map.is_tile_walkable(b.0, b.1));
+                match (a.0.abs_diff(b.0), a.1.abs_diff(b.1)) {
+                    (1, 0) | (0, 1) => 1.0,
+                    (1, 1) => DIAGONAL_COST,
+                    other => panic!("not a single step: {:?}", other),
+                }
+            })
+            .sum()
+    }
+
+    const OPEN: [&str; 6] = ["######", "#....#", "#....#", "#....#", "#....#", "######"];
+
+    #[test]
+    fn shortest_path_with_four_connectivity() {
+        let map = map(&["#######", "#.....#", "#.###.#", "#.....#", "#######"]);
+        let path = find_path(&map, (1, 1), (5, 3), Connectivity::Four).unwrap();
+        assert_eq!(path.first(), Some(&(1, 1)));
+        assert_eq!(path.last(), Some(&(5, 3)));
+        assert_eq!(path_cost(&map, &path), 6.0);
+
+        let open = self::map(&OPEN);
+        let path = find_path(&open, (1, 1), (4, 3), Connectivity::Four).unwrap();
+        assert_eq!(path_cost(&open, &path), 5.0);
+    }
+
+    #[test]
+    fn shortest_path_with_eight_connectivity() {
+        let map = map(&OPEN);
+        let path = find_path(&map, (1, 1), (4, 3), Connectivity::Eight).unwrap();
+        assert_eq!(path.len(), 4);
+        assert!((path_cost(&map, &path) - (2.0 * DIAGONAL_COST + 1.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unreachable_target_returns_none() {
+        let map = map(&["#######", "#..#..#", "#..#..#", "#######"]);
+        assert_eq!(find_path(&map, (1, 1), (5, 2), Connectivity::Four), None);
+        assert_eq!(find_path(&map, (1, 1), (5, 2), Connectivity::Eight), None);
+        // 终点是墙
+        assert_eq!(find_path(&map, (1, 1), (3, 1), Connectivity::Four), None);
+    }
+
+    #[test]
+    fn start_equal_to_goal() {
+        let map = map(&OPEN);
+        assert_eq!(find_path(&map, (2, 2), (2, 2), Connectivity::Eight), Some(vec![(2, 2)]));
+    }
+
+    #[test]
+    fn does_not_cut_corners() {
+        // 两个障碍物斜着相邻，中间不能斜穿过去
+        let blocked = map(&["#####", "#.o.#", "#o..#", "#####"]);
+        assert_eq!(find_path(&blocked, (1, 1), (2, 2), Connectivity::Eight), None);
+
+        // 只有一侧有障碍物时也要绕过它的角
+        let one_side = map(&["#####", "#.o.#", "#...#", "#####"]);
+        let path = find_path(&one_side, (1, 1), (2, 2), Connectivity::Eight).unwrap();
+        assert_eq!(path, vec![(1, 1), (1, 2), (2, 2)]);
+    }
+}
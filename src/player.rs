@@ -1,30 +1,64 @@
 // src/player.rs
-use wasm_bindgen::prelude::*;
-use web_sys::console;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy)]
+use crate::map::GameMap;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Position {
     pub x: f64,
     pub y: f64,
 }
 
-#[wasm_bindgen]
-#[derive(Clone, Copy)]
+// 速度（像素/秒）
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Velocity {
+    pub x: f64,
+    pub y: f64,
+}
+
+// 移动手感参数
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Movement {
+    // 按住方向键时的加速度（像素/秒²）
+    pub acceleration: f64,
+    // 最大速度（像素/秒）
+    pub max_speed: f64,
+    // 松开方向键后的减速度（像素/秒²）
+    pub friction: f64,
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Movement {
+            acceleration: 2400.0,
+            max_speed: 300.0,
+            friction: 2000.0,
+        }
+    }
+}
+
+// 玩家的边长（像素）
+pub const PLAYER_SIZE: f64 = 20.0;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Player {
     position: Position,
-    speed: f64,
+    // 版本 1 的存档没有这两个字段
+    #[serde(default)]
+    velocity: Velocity,
+    #[serde(default)]
+    movement: Movement,
     size: f64,
 }
 
-#[wasm_bindgen]
 impl Player {
-    #[wasm_bindgen(constructor)]
     pub fn new(x: f64, y: f64) -> Player {
-        console::log_1(&"Creating new player".into());
         Player {
             position: Position { x, y },
-            speed: 5.0,
-            size: 20.0,
+            velocity: Velocity::default(),
+            movement: Movement::default(),
+            size: PLAYER_SIZE,
         }
     }
 
@@ -40,29 +74,78 @@ impl Player {
         self.size
     }
 
-    pub fn move_up(&mut self) {
-        self.position.y -= self.speed;
+    pub fn velocity(&self) -> Velocity {
+        self.velocity
     }
 
-    pub fn move_down(&mut self, max_height: f64) {
-        let new_y = self.position.y + self.speed;
-        if new_y + self.size <= max_height {
-            self.position.y = new_y;
-        }
+    pub fn movement(&self) -> Movement {
+        self.movement
+    }
+
+    pub fn set_movement(&mut self, movement: Movement) {
+        self.movement = movement;
+    }
+
+    pub fn set_position(&mut self, x: f64, y: f64) {
+        self.position = Position { x, y };
     }
 
-    pub fn move_left(&mut self) {
-        self.position.x -= self.speed;
+    pub fn set_velocity(&mut self, x: f64, y: f64) {
+        self.velocity = Velocity { x, y };
     }
 
-    pub fn move_right(&mut self, max_width: f64) {
-        let new_x = self.position.x + self.speed;
-        if new_x + self.size <= max_width {
-            self.position.x = new_x;
+    // 按当前速度移动并与地图碰撞。X 和 Y 分开处理，
+    // 所以斜着撞墙时会沿着墙滑动，而且会贴紧墙的边缘
+    pub fn move_and_slide(&mut self, map: &GameMap, delta_time: f64) {
+        let (x, blocked_x) = map.sweep_x(
+            self.position.x,
+            self.position.y,
+            self.size,
+            self.size,
+            self.velocity.x * delta_time,
+        );
+        self.position.x = x;
+        if blocked_x {
+            self.velocity.x = 0.0;
+        }
+
+        let (y, blocked_y) = map.sweep_y(
+            self.position.x,
+            self.position.y,
+            self.size,
+            self.size,
+            self.velocity.y * delta_time,
+        );
+        self.position.y = y;
+        if blocked_y {
+            self.velocity.y = 0.0;
         }
     }
 
-    pub fn update(&mut self) {
-        // 将来可以在这里添加更多的更新逻辑
+    // 根据方向输入更新速度。(dir_x, dir_y) 是 -1 到 1 的方向，斜向时会归一化，
+    // 所以斜着走不会比直着走更快
+    pub fn accelerate(&mut self, dir_x: f64, dir_y: f64, delta_time: f64) {
+        let length = (dir_x * dir_x + dir_y * dir_y).sqrt();
+        let (target_x, target_y, rate) = if length > 0.0 {
+            (
+                dir_x / length * self.movement.max_speed,
+                dir_y / length * self.movement.max_speed,
+                self.movement.acceleration,
+            )
+        } else {
+            (0.0, 0.0, self.movement.friction)
+        };
+
+        // 速度以 rate 为上限向目标速度靠近
+        let dx = target_x - self.velocity.x;
+        let dy = target_y - self.velocity.y;
+        let difference = (dx * dx + dy * dy).sqrt();
+        let max_change = rate * delta_time;
+        if difference <= max_change {
+            self.velocity = Velocity { x: target_x, y: target_y };
+        } else {
+            self.velocity.x += dx / difference * max_change;
+            self.velocity.y += dy / difference * max_change;
+        }
     }
-}
\ No newline at end of file
+}
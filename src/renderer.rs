@@ -1,7 +1,7 @@
 // src/renderer.rs
 use wasm_bindgen::prelude::*;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
-use crate::{game::GameState, Game};
+use crate::{guard::GuardMode, simulation::{GameOverReason, GameState}, treasure::TreasureKind, Game};
 
 #[wasm_bindgen]
 pub struct Renderer {
@@ -35,21 +35,58 @@ impl Renderer {
 
     #[wasm_bindgen]
     pub fn render(&self, game: &Game) {
+        // 玩家按固定步长移动，绘制时在两步之间插值
+        let alpha = game.get_interpolation_alpha();
         self.clear();
+
+        // 地图上的东西用世界坐标绘制，由摄像机变换到画布上；HUD 和各种画面直接用画布坐标
+        let camera = game.camera();
+        let zoom = camera.zoom();
+        // 平移取整到像素，避免格子之间出现缝隙
+        self.context.set_transform(
+            zoom,
+            0.0,
+            0.0,
+            zoom,
+            (-camera.x() * zoom).round(),
+            (-camera.y() * zoom).round(),
+        ).unwrap();
         self.render_map(game);
-        self.render_player(game);
+        self.render_player(game, alpha);
         self.render_treasures(game);
+        self.render_guards(game);
+        self.render_hint_arrow(game, alpha);
+        self.context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+
         self.render_ui(game);
-        match game.get_state() {
-            GameState::Won => self.render_victory_screen(game),
-            GameState::TimeUp => self.render_game_over_screen(),
-            _ => {}
+        match game.simulation().state() {
+            GameState::Menu => self.render_menu_screen(),
+            GameState::LevelIntro => self.render_intro_screen(game),
+            GameState::Playing => {}
+            GameState::Paused => self.render_pause_screen(),
+            GameState::Results => self.render_victory_screen(game),
+            GameState::GameOver => self.render_game_over_screen(game),
         }
     }
 
+    // 开启战争迷雾时，没探索过的格子画成黑色，探索过但当前看不到的格子画暗一些
     fn render_map(&self, game: &Game) {
-        for y in 0..game.get_map_height() {
-            for x in 0..game.get_map_width() {
+        let fog = game.simulation().config().fog_of_war;
+        let map = game.simulation().map();
+        // 只画视口内的格子
+        let (x0, x1, y0, y1) = game.camera().visible_tiles(map.tile_size(), map.width(), map.height());
+        for y in y0..y1 {
+            for x in x0..x1 {
+                if fog && !map.is_explored(x, y) {
+                    self.context.set_fill_style_str("#000000");
+                    self.context.fill_rect(
+                        x as f64 * game.get_map_tile_size(),
+                        y as f64 * game.get_map_tile_size(),
+                        game.get_map_tile_size(),
+                        game.get_map_tile_size(),
+                    );
+                    continue;
+                }
                 let tile = game.get_map_tile(x, y);
                 let color = match tile {
                     0 => "#FFFFFF", // Empty
@@ -58,49 +95,195 @@ impl Renderer {
                     _ => "#FF0000", // Error case
                 };
 
-                self.context.set_fill_style(&JsValue::from_str(color));
+                self.context.set_fill_style_str(color);
                 self.context.fill_rect(
                     x as f64 * game.get_map_tile_size(),
                     y as f64 * game.get_map_tile_size(),
                     game.get_map_tile_size(),
                     game.get_map_tile_size(),
                 );
+                if fog && !map.is_visible(x, y) {
+                    self.context.set_fill_style_str("rgba(0, 0, 0, 0.45)");
+                    self.context.fill_rect(
+                        x as f64 * game.get_map_tile_size(),
+                        y as f64 * game.get_map_tile_size(),
+                        game.get_map_tile_size(),
+                        game.get_map_tile_size(),
+                    );
+                }
             }
         }
     }
 
-    fn render_player(&self, game: &Game) {
-        let player = game.player();
-        self.context.set_fill_style(&JsValue::from_str("#0000FF"));
+    // 开启战争迷雾时只画玩家当前能看到的东西
+    fn is_hidden(&self, game: &Game, x: f64, y: f64) -> bool {
+        let map = game.simulation().map();
+        let (tile_x, tile_y) = map.tile_at(x, y);
+        game.simulation().config().fog_of_war && !map.is_visible(tile_x, tile_y)
+    }
+
+    fn render_player(&self, game: &Game, alpha: f64) {
+        let player = game.simulation().player();
+        let (x, y) = game.simulation().interpolated_player_position(alpha);
+        // 被抓到后的保护时间内闪烁
+        let grace = game.simulation().catch_grace();
+        if grace > 0.0 && ((grace * 8.0) as u32).is_multiple_of(2) {
+            self.context.set_fill_style_str("rgba(0, 0, 255, 0.3)");
+        } else {
+            self.context.set_fill_style_str("#0000FF");
+        }
         self.context.fill_rect(
-            player.x(),
-            player.y(),
+            x,
+            y,
             player.size(),
             player.size(),
         );
     }
 
     fn render_treasures(&self, game: &Game) {
-        for i in 0..game.get_treasure_count() {
-            if !game.is_treasure_collected(i) {
-                if let (Some(x), Some(y)) = (game.get_treasure_x(i), game.get_treasure_y(i)) {
-                    self.context.set_fill_style(&JsValue::from_str("#FFD700")); // 金色
+        for treasure in game.simulation().treasures().treasures() {
+            if treasure.is_collected() || self.is_hidden(game, treasure.x(), treasure.y()) {
+                continue;
+            }
+            let (x, y) = (treasure.x(), treasure.y());
+            let radius = treasure.size() / 2.0;
+            match treasure.kind() {
+                TreasureKind::Coin => {
+                    self.context.set_fill_style_str("#FFD700"); // 金色
+                    self.fill_circle(x, y, radius);
+                }
+                TreasureKind::Gem => {
+                    // 青色菱形
+                    self.context.set_fill_style_str("#00CED1");
                     self.context.begin_path();
-                    self.context.arc(
-                        x,
-                        y,
-                        10.0, // 宝藏半径
-                        0.0,
-                        2.0 * std::f64::consts::PI,
-                    ).unwrap();
+                    self.context.move_to(x, y - radius);
+                    self.context.line_to(x + radius, y);
+                    self.context.line_to(x, y + radius);
+                    self.context.line_to(x - radius, y);
+                    self.context.close_path();
                     self.context.fill();
                 }
+                TreasureKind::Chest => {
+                    // 棕色箱子加金色锁扣
+                    self.context.set_fill_style_str("#8B4513");
+                    self.context.fill_rect(x - radius, y - radius * 0.7, radius * 2.0, radius * 1.4);
+                    self.context.set_fill_style_str("#FFD700");
+                    self.context.fill_rect(x - 3.0, y - 3.0, 6.0, 6.0);
+                }
+                TreasureKind::Hourglass => {
+                    // 上下两个三角形
+                    self.context.set_fill_style_str("#1E90FF");
+                    self.context.begin_path();
+                    self.context.move_to(x - radius, y - radius);
+                    self.context.line_to(x + radius, y - radius);
+                    self.context.line_to(x - radius, y + radius);
+                    self.context.line_to(x + radius, y + radius);
+                    self.context.close_path();
+                    self.context.fill();
+                }
+                TreasureKind::Cursed => {
+                    // 紫色圆加黑色叉
+                    self.context.set_fill_style_str("#800080");
+                    self.fill_circle(x, y, radius);
+                    self.context.set_stroke_style_str("#000000");
+                    self.context.set_line_width(2.0);
+                    self.context.begin_path();
+                    self.context.move_to(x - radius / 2.0, y - radius / 2.0);
+                    self.context.line_to(x + radius / 2.0, y + radius / 2.0);
+                    self.context.move_to(x + radius / 2.0, y - radius / 2.0);
+                    self.context.line_to(x - radius / 2.0, y + radius / 2.0);
+                    self.context.stroke();
+                }
+                TreasureKind::Compass => {
+                    // 白色表盘加红色指针
+                    self.context.set_fill_style_str("#F5F5F5");
+                    self.fill_circle(x, y, radius);
+                    self.context.set_stroke_style_str("#333333");
+                    self.context.set_line_width(2.0);
+                    self.context.stroke();
+                    self.context.set_fill_style_str("#DC143C");
+                    self.context.begin_path();
+                    self.context.move_to(x, y - radius * 0.8);
+                    self.context.line_to(x + radius * 0.3, y);
+                    self.context.line_to(x - radius * 0.3, y);
+                    self.context.close_path();
+                    self.context.fill();
+                }
+            }
+        }
+    }
+
+    // 指南针生效时在玩家旁边画一个指向最近宝藏的箭头
+    fn render_hint_arrow(&self, game: &Game, alpha: f64) {
+        let Some((dx, dy)) = game.simulation().hint_direction() else {
+            return;
+        };
+        let half = game.simulation().player().size() / 2.0;
+        let (x, y) = game.simulation().interpolated_player_position(alpha);
+        let (center_x, center_y) = (x + half, y + half);
+        let (tip_x, tip_y) = (center_x + dx * 34.0, center_y + dy * 34.0);
+        let (base_x, base_y) = (center_x + dx * 20.0, center_y + dy * 20.0);
+
+        self.context.set_fill_style_str("#DC143C");
+        self.context.begin_path();
+        self.context.move_to(tip_x, tip_y);
+        self.context.line_to(base_x - dy * 7.0, base_y + dx * 7.0);
+        self.context.line_to(base_x + dy * 7.0, base_y - dx * 7.0);
+        self.context.close_path();
+        self.context.fill();
+    }
+
+    fn render_guards(&self, game: &Game) {
+        for guard in game.simulation().guards() {
+            let size = guard.size();
+            let (center_x, center_y) = (guard.x() + size / 2.0, guard.y() + size / 2.0);
+            if self.is_hidden(game, center_x, center_y) {
+                continue;
+            }
+
+            // 半透明的视野扇形（不考虑墙的遮挡），追赶时不画
+            if !guard.is_chasing() && !guard.is_stunned() {
+                let (facing_x, facing_y) = guard.facing();
+                let angle = facing_y.atan2(facing_x);
+                self.context.set_fill_style_str("rgba(255, 0, 0, 0.12)");
+                self.context.begin_path();
+                self.context.move_to(center_x, center_y);
+                self.context.arc(
+                    center_x,
+                    center_y,
+                    guard.view_range(),
+                    angle - std::f64::consts::FRAC_PI_3,
+                    angle + std::f64::consts::FRAC_PI_3,
+                ).unwrap();
+                self.context.close_path();
+                self.context.fill();
+            }
+
+            let color = match guard.mode() {
+                GuardMode::Patrol | GuardMode::Return => "#B22222",
+                GuardMode::Chase => "#FF0000",
+                GuardMode::Search { .. } => "#FF8C00",
+                GuardMode::Stunned { .. } => "#A9A9A9",
+            };
+            self.context.set_fill_style_str(color);
+            self.context.fill_rect(guard.x(), guard.y(), size, size);
+
+            // 追赶时头上显示感叹号
+            if guard.is_chasing() {
+                self.context.set_font("bold 18px Arial");
+                self.context.fill_text("!", center_x - 3.0, guard.y() - 4.0).unwrap();
             }
         }
     }
 
+    fn fill_circle(&self, x: f64, y: f64, radius: f64) {
+        self.context.begin_path();
+        self.context.arc(x, y, radius, 0.0, 2.0 * std::f64::consts::PI).unwrap();
+        self.context.fill();
+    }
+
     fn render_ui(&self, game: &Game) {
-        self.context.set_fill_style(&JsValue::from_str("#000000"));
+        self.context.set_fill_style_str("#000000");
         self.context.set_font("20px Arial");
         
         // 显示分数
@@ -121,16 +304,96 @@ impl Renderer {
         // 显示最佳时间（如果有）
         if let Some(best_time) = game.get_best_time() {
             self.context.fill_text(
-                &format!("Best: {:.1}", best_time),
+                &format!("Best ({}): {:.1}", game.get_difficulty(), best_time),
+                10.0,
+                90.0,
+            ).unwrap();
+        }
+
+        // 连击倍率和剩余窗口
+        if game.get_combo_multiplier() > 1.0 {
+            self.context.set_fill_style_str("#CC6600");
+            self.context.fill_text(
+                &format!("Combo x{:.1} ({})", game.get_combo_multiplier(), game.get_combo_chain()),
                 10.0,
+                120.0,
+            ).unwrap();
+            self.context.fill_rect(10.0, 128.0, 120.0 * game.get_combo_remaining(), 4.0);
+            self.context.set_fill_style_str("#000000");
+        }
+
+        // 右上角的信息在战争迷雾中加一块底色，免得和黑色的未探索区域混在一起
+        if game.simulation().config().fog_of_war {
+            self.context.set_fill_style_str("rgba(255, 255, 255, 0.7)");
+            self.context.fill_rect(self.width as f64 - 180.0, 5.0, 175.0, 95.0);
+            self.context.set_fill_style_str("#000000");
+        }
+
+        // 战役中显示关卡进度
+        if game.level_count() > 1 {
+            self.context.fill_text(
+                &format!("Level: {}/{}", game.current_level() + 1, game.level_count()),
+                self.width as f64 - 170.0,
+                30.0,
+            ).unwrap();
+        }
+
+        // 探索进度
+        if game.simulation().config().fog_of_war {
+            self.context.fill_text(
+                &format!("Explored: {:.0}%", game.get_exploration() * 100.0),
+                self.width as f64 - 170.0,
                 90.0,
             ).unwrap();
         }
+
+        // 按到最近宝藏的路程显示冷热
+        if let Some(distance) = game.get_treasure_distance() {
+            let (label, color) = match distance {
+                d if d <= 3.0 => ("Hot!", "#FF2200"),
+                d if d <= 7.0 => ("Warm", "#FF8C00"),
+                d if d <= 12.0 => ("Cool", "#4682B4"),
+                _ => ("Cold", "#0000CD"),
+            };
+            self.context.set_fill_style_str(color);
+            self.context.fill_text(label, self.width as f64 - 170.0, 60.0).unwrap();
+            self.context.set_fill_style_str("#000000");
+        }
+    }
+
+    fn render_menu_screen(&self) {
+        self.render_overlay();
+        self.context.set_fill_style_str("#000000");
+        self.context.set_font("40px Arial");
+        self.context.fill_text(
+            "Treasure Hunt",
+            self.width as f64 / 2.0 - 125.0,
+            self.height as f64 / 2.0 - 20.0,
+        ).unwrap();
+        self.context.set_font("20px Arial");
+        self.context.fill_text(
+            "Press Start to play",
+            self.width as f64 / 2.0 - 85.0,
+            self.height as f64 / 2.0 + 20.0,
+        ).unwrap();
+    }
+
+    // 开场倒计时：3、2、1
+    fn render_intro_screen(&self, game: &Game) {
+        self.render_overlay();
+        self.context.set_fill_style_str("#000000");
+        self.context.set_font("60px Arial");
+        let count = game.get_intro_remaining().ceil().max(1.0);
+        self.context.fill_text(
+            &format!("{}", count),
+            self.width as f64 / 2.0 - 15.0,
+            self.height as f64 / 2.0 + 20.0,
+        ).unwrap();
     }
 
     fn render_victory_screen(&self, game: &Game) {
         self.render_overlay();
-        self.context.set_fill_style(&JsValue::from_str("#000000"));
+        self.context.set_fill_style_str("#000000");
         self.context.set_font("40px Arial");
         self.context.fill_text("Victory!", self.width as f64 / 2.0 - 70.0, self.height as f64 / 2.0 - 40.0).unwrap();
         self.context.set_font("20px Arial");
@@ -139,22 +402,83 @@ impl Renderer {
             self.width as f64 / 2.0 - 80.0,
             self.height as f64 / 2.0,
         ).unwrap();
+
+        // 战役中提示下一关或通关
+        if game.level_count() > 1 {
+            let message = if game.has_next_level() {
+                "Press Start for the next level"
+            } else {
+                "Campaign complete!"
+            };
+            self.context.fill_text(
+                message,
+                self.width as f64 / 2.0 - 130.0,
+                self.height as f64 / 2.0 + 40.0,
+            ).unwrap();
+        }
+
+        if !game.has_next_level() {
+            self.render_leaderboard(game, self.height as f64 / 2.0 + 80.0);
+        }
     }
 
-    fn render_game_over_screen(&self) {
+    // 排行榜，本局的成绩用红色标出
+    fn render_leaderboard(&self, game: &Game, top: f64) {
+        let left = self.width as f64 / 2.0 - 190.0;
+        self.context.set_fill_style_str("#000000");
+        self.context.set_font("bold 16px Arial");
+        self.context.fill_text("High Scores", left, top).unwrap();
+
+        self.context.set_font("16px Arial");
+        for (rank, entry) in game.leaderboard().entries().iter().enumerate() {
+            let color = if game.get_last_rank() == Some(rank) { "#CC0000" } else { "#000000" };
+            self.context.set_fill_style_str(color);
+
+            let date = js_sys::Date::new(&JsValue::from_f64(entry.date));
+            let line = format!(
+                "{:>2}. {}  {}  {:.1}s  {}  {:04}-{:02}-{:02}",
+                rank + 1,
+                entry.name,
+                entry.score,
+                entry.time,
+                entry.difficulty.name(),
+                date.get_full_year(),
+                date.get_month() + 1,
+                date.get_date(),
+            );
+            self.context.fill_text(&line, left, top + 20.0 * (rank + 1) as f64).unwrap();
+        }
+    }
+
+    fn render_game_over_screen(&self, game: &Game) {
         self.render_overlay();
-        self.context.set_fill_style(&JsValue::from_str("#000000"));
+        self.context.set_fill_style_str("#000000");
         self.context.set_font("40px Arial");
+        let (title, offset) = match game.simulation().game_over_reason() {
+            GameOverReason::TimeUp => ("Time's Up!", 80.0),
+            GameOverReason::Caught => ("Caught!", 65.0),
+        };
         self.context.fill_text(
-            "Time's Up!",
-            self.width as f64 / 2.0 - 80.0,
+            title,
+            self.width as f64 / 2.0 - offset,
+            self.height as f64 / 2.0,
+        ).unwrap();
+    }
+
+    fn render_pause_screen(&self) {
+        self.render_overlay();
+        self.context.set_fill_style_str("#000000");
+        self.context.set_font("40px Arial");
+        self.context.fill_text(
+            "Paused",
+            self.width as f64 / 2.0 - 60.0,
             self.height as f64 / 2.0,
         ).unwrap();
     }
 
     fn render_overlay(&self) {
         self.context.set_global_alpha(0.7);
-        self.context.set_fill_style(&JsValue::from_str("#FFFFFF"));
+        self.context.set_fill_style_str("#FFFFFF");
         self.context.fill_rect(0.0, 0.0, self.width as f64, self.height as f64);
         self.context.set_global_alpha(1.0);
     }
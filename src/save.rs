@@ -0,0 +1,290 @@
+// src/save.rs
+// 基于 serde 的 JSON 存档与关卡交换格式，带版本号以便将来升级
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::campaign::Campaign;
+use crate::combo::Combo;
+use crate::config::{GameConfig, MAX_MAP_TILES, MIN_MAP_TILES};
+use crate::difficulty::Difficulty;
+use crate::guard::Guard;
+use crate::level::{Level, LevelError};
+use crate::map::{GameMap, MapGenerator};
+use crate::player::{Player, PLAYER_SIZE};
+use crate::simulation::{GameOverReason, GameState};
+use crate::treasure::Treasure;
+
+// 当前存档格式版本。结构只能向后兼容地修改：新增的字段必须带 #[serde(default)]，旧存档缺少时取默认值；
+// 只有丢失后会重新计算的字段（例如守卫回到路线的路径）可以改名或移除。
+// 删除其它字段、改变字段的类型或含义时需要加一
+pub const SAVE_VERSION: u32 = 2;
+
+// 仍然能读取的最早版本。版本 1 的玩家没有速度和移动参数，读取时玩家静止、使用默认手感
+pub const MIN_SAVE_VERSION: u32 = 1;
+
+// 一局游戏的完整快照
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SaveState {
+    pub version: u32,
+    pub seed: u32,
+    pub generator: MapGenerator,
+    #[serde(default)]
+    pub level: Option<Level>,
+    pub map: GameMap,
+    pub spawn: (usize, usize),
+    pub player: Player,
+    pub treasures: Vec<Treasure>,
+    #[serde(default)]
+    pub guards: Vec<Guard>,
+    #[serde(default)]
+    pub catch_grace: f64,
+    #[serde(default)]
+    pub hint_remaining: f64,
+    pub score: u32,
+    #[serde(default)]
+    pub combo: Combo,
+    pub game_time: f64,
+    pub time_limit: f64,
+    pub best_time: Option<f64>,
+    pub state: GameState,
+    #[serde(default)]
+    pub game_over_reason: GameOverReason,
+    #[serde(default)]
+    pub is_running: bool,
+    #[serde(default)]
+    pub intro_remaining: f64,
+    #[serde(default)]
+    pub campaign: Option<Campaign>,
+    #[serde(default)]
+    pub level_start_score: u32,
+    #[serde(default)]
+    pub config: GameConfig,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    #[serde(default)]
+    pub best_times: BTreeMap<Difficulty, f64>,
+}
+
+// 用于在工具之间交换的关卡文件
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LevelFile {
+    pub version: u32,
+    pub level: Level,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SaveError {
+    Json(String),
+    UnsupportedVersion { found: u32, supported: u32 },
+    InvalidMap,
+    InvalidTileSize,
+    InvalidMapSize { width: usize, height: usize },
+    InvalidLevel(LevelError),
+    InvalidCampaign,
+    InvalidGuard,
+    InvalidConfig(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Json(message) => write!(f, "invalid save data: {}", message),
+            SaveError::UnsupportedVersion { found, supported } => {
+                write!(f, "unsupported save version {} (supported: {} to {})", found, MIN_SAVE_VERSION, supported)
+            }
+            SaveError::InvalidMap => write!(f, "map size does not match its tiles"),
+            SaveError::InvalidTileSize => write!(f, "tile size must be at least the player size ({})", PLAYER_SIZE),
+            SaveError::InvalidMapSize { width, height } => write!(
+                f,
+                "map is {}x{} tiles, it must be between {}x{} and {}x{}",
+                width, height, MIN_MAP_TILES, MIN_MAP_TILES, MAX_MAP_TILES, MAX_MAP_TILES
+            ),
+            SaveError::InvalidLevel(error) => write!(f, "invalid level: {}", error),
+            SaveError::InvalidCampaign => write!(f, "campaign level is out of range"),
+            SaveError::InvalidGuard => write!(f, "guard route is empty or leaves the map"),
+            SaveError::InvalidConfig(message) => write!(f, "invalid save data: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(error: serde_json::Error) -> Self {
+        SaveError::Json(error.to_string())
+    }
+}
+
+impl SaveState {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("save state is always serializable")
+    }
+
+    pub fn from_json(text: &str) -> Result<SaveState, SaveError> {
+        check_version(text)?;
+        let save: SaveState = serde_json::from_str(text)?;
+        save.validate()?;
+        Ok(save)
+    }
+
+    // 检查反序列化时无法保证的约束，存档可能来自被修改过的文件
+    pub fn validate(&self) -> Result<(), SaveError> {
+        if !(MIN_SAVE_VERSION..=SAVE_VERSION).contains(&self.version) {
+            return Err(SaveError::UnsupportedVersion { found: self.version, supported: SAVE_VERSION });
+        }
+        validate_map(&self.map, self.spawn)?;
+        if let Some(level) = &self.level {
+            validate_level(level)?;
+        }
+        if self.campaign.as_ref().is_some_and(|campaign| !campaign.is_consistent()) {
+            return Err(SaveError::InvalidCampaign);
+        }
+        if self.guards.iter().any(|guard| !guard.is_consistent(&self.map)) {
+            return Err(SaveError::InvalidGuard);
+        }
+        self.config.validate().map_err(|e| SaveError::InvalidConfig(e.to_string()))?;
+        Ok(())
+    }
+}
+
+impl LevelFile {
+    pub fn new(level: Level) -> LevelFile {
+        LevelFile { version: SAVE_VERSION, level }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("level is always serializable")
+    }
+
+    pub fn from_json(text: &str) -> Result<Level, SaveError> {
+        check_version(text)?;
+        let file: LevelFile = serde_json::from_str(text)?;
+        validate_level(&file.level)?;
+        Ok(file.level)
+    }
+}
+
+// 先只读取版本号，这样旧版本或更新版本的存档能得到明确的错误而不是字段缺失
+fn check_version(text: &str) -> Result<(), SaveError> {
+    #[derive(Deserialize)]
+    struct VersionProbe {
+        version: u32,
+    }
+
+    let probe: VersionProbe = serde_json::from_str(text)?;
+    if !(MIN_SAVE_VERSION..=SAVE_VERSION).contains(&probe.version) {
+        return Err(SaveError::UnsupportedVersion { found: probe.version, supported: SAVE_VERSION });
+    }
+    Ok(())
+}
+
+// 格子大小和地图大小使用与 GameConfig 相同的限制，太小的格子会让碰撞检测出错
+fn validate_map(map: &GameMap, spawn: (usize, usize)) -> Result<(), SaveError> {
+    if !map.is_consistent() || spawn.0 >= map.width() || spawn.1 >= map.height() {
+        return Err(SaveError::InvalidMap);
+    }
+    if !map.tile_size().is_finite() || map.tile_size() < PLAYER_SIZE {
+        return Err(SaveError::InvalidTileSize);
+    }
+    let sizes = MIN_MAP_TILES..=MAX_MAP_TILES;
+    if !sizes.contains(&map.width()) || !sizes.contains(&map.height()) {
+        return Err(SaveError::InvalidMapSize { width: map.width(), height: map.height() });
+    }
+    Ok(())
+}
+
+// 与文本关卡使用同一套规则检查出生点和宝藏
+pub fn validate_level(level: &Level) -> Result<(), SaveError> {
+    validate_map(&level.map, level.spawn)?;
+    level.validate().map_err(SaveError::InvalidLevel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::TileType;
+
+    fn level() -> Level {
+        Level::parse("#####\n#P.T#\n#####\n", 40.0).unwrap()
+    }
+
+    #[test]
+    fn level_file_round_trips() {
+        let json = LevelFile::new(level()).to_json();
+        let loaded = LevelFile::from_json(&json).unwrap();
+        assert_eq!(loaded.to_level_string(), level().to_level_string());
+    }
+
+    #[test]
+    fn level_file_rejects_treasure_on_the_border() {
+        let mut level = level();
+        level.treasures.push((4, 1));
+        let json = LevelFile::new(level).to_json();
+        assert_eq!(
+            LevelFile::from_json(&json).err(),
+            Some(SaveError::InvalidLevel(LevelError::TreasureOutOfBounds { line: 2, column: 5 }))
+        );
+    }
+
+    #[test]
+    fn level_file_rejects_treasure_off_the_map() {
+        let mut level = level();
+        level.treasures = vec![(10, 10)];
+        let json = LevelFile::new(level).to_json();
+        assert!(matches!(LevelFile::from_json(&json), Err(SaveError::InvalidLevel(_))));
+    }
+
+    #[test]
+    fn level_file_rejects_treasure_in_a_wall() {
+        let mut level = level();
+        level.treasures = vec![(2, 1)];
+        level.map.set_tile(2, 1, TileType::Obstacle);
+        let json = LevelFile::new(level).to_json();
+        assert_eq!(
+            LevelFile::from_json(&json).err(),
+            Some(SaveError::InvalidLevel(LevelError::TreasureBlocked { line: 2, column: 3 }))
+        );
+    }
+
+    #[test]
+    fn level_file_rejects_bad_tile_sizes() {
+        for tile_size in ["0.0", "-40.0", "10.0", "1e400"] {
+            let json = LevelFile::new(level()).to_json().replace("\"tile_size\":40.0", &format!("\"tile_size\":{}", tile_size));
+            assert!(
+                matches!(LevelFile::from_json(&json), Err(SaveError::InvalidTileSize) | Err(SaveError::Json(_))),
+                "{}",
+                tile_size
+            );
+        }
+    }
+
+    #[test]
+    fn level_file_rejects_huge_maps() {
+        let width = MAX_MAP_TILES + 1;
+        let mut tiles = vec![TileType::Empty; width * 3];
+        for x in 0..width {
+            tiles[x] = TileType::Wall;
+            tiles[2 * width + x] = TileType::Wall;
+        }
+        let level = Level { map: GameMap::from_tiles(width, 3, 40.0, tiles), spawn: (1, 1), treasures: vec![(2, 1)] };
+        assert_eq!(
+            LevelFile::from_json(&LevelFile::new(level).to_json()).err(),
+            Some(SaveError::InvalidMapSize { width, height: 3 })
+        );
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let json = LevelFile::new(level()).to_json().replacen(
+            &format!("\"version\":{}", SAVE_VERSION),
+            &format!("\"version\":{}", SAVE_VERSION + 1),
+            1,
+        );
+        assert_eq!(
+            LevelFile::from_json(&json).err(),
+            Some(SaveError::UnsupportedVersion { found: SAVE_VERSION + 1, supported: SAVE_VERSION })
+        );
+    }
+}
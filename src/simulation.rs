@@ -0,0 +1,1134 @@
+// src/simulation.rs
+// 与平台无关的游戏核心逻辑，不依赖 wasm-bindgen / web-sys，可在原生 Rust 中运行
+use std::collections::BTreeMap;
+
+use crate::campaign::{Campaign, LevelSource};
+use crate::config::{ConfigError, GameConfig};
+use crate::difficulty::Difficulty;
+use crate::distance_field::DistanceField;
+use crate::guard::{self, Guard, GuardContact};
+use crate::player::{Movement, Player};
+use crate::map::{GameMap, MapGenerator, TileType};
+use crate::treasure::{Collected, TreasureKind, TreasureManager};
+use crate::level::Level;
+use crate::save::{SaveError, SaveState, SAVE_VERSION};
+use crate::rng::Rng;
+use crate::state::INTRO_DURATION;
+
+pub use crate::state::{GameOverReason, GameState};
+
+// 随机生成地图时玩家出生的格子（左上角）
+pub const SPAWN_TILE: (usize, usize) = (1, 1);
+
+// 默认每秒模拟步数
+pub const DEFAULT_TICK_RATE: f64 = 60.0;
+
+// 每秒模拟步数的范围，太高时一帧要模拟的步数过多会卡住页面
+pub const MIN_TICK_RATE: f64 = 10.0;
+pub const MAX_TICK_RATE: f64 = 1000.0;
+
+// 单帧最多计入的时间（秒）
+const MAX_FRAME_TIME: f64 = 0.25;
+
+// 被守卫抓到后不会再次被抓到的秒数
+const CATCH_GRACE: f64 = 3.0;
+
+// 游戏中发生的事件，由前端决定如何反馈（音效、日志等）
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameEvent {
+    // points 是乘上连击倍率后的得分，chain 是当前连击中的第几个（连击中断时为 0）
+    TreasureCollected { kind: TreasureKind, points: i64, chain: u32 },
+    // 状态机发生了切换，例如进入 Results 表示胜利、进入 GameOver 表示时间用完
+    StateChanged { from: GameState, to: GameState },
+    // 守卫发现了玩家，开始追赶
+    GuardAlerted,
+    // 玩家被守卫抓到，contact 是这次的后果
+    PlayerCaught { contact: GuardContact },
+}
+
+// 当前按下的方向键
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Input {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl Input {
+    // 方向键对应的方向（未归一化），相反方向同时按下时互相抵消
+    pub fn direction(&self) -> (f64, f64) {
+        let mut x = 0.0;
+        let mut y = 0.0;
+        if self.up {
+            y -= 1.0;
+        }
+        if self.down {
+            y += 1.0;
+        }
+        if self.left {
+            x -= 1.0;
+        }
+        if self.right {
+            x += 1.0;
+        }
+        (x, y)
+    }
+}
+
+pub struct Simulation {
+    // 地图的像素大小
+    width: u32,
+    height: u32,
+    // 创建时给出的画布大小，config 没有指定地图格子数时按它铺满
+    canvas_size: (u32, u32),
+    tile_size: f64,
+    player: Player,
+    map: GameMap,
+    input: Input,
+    treasure_manager: TreasureManager,
+    guards: Vec<Guard>,
+    // 到最近的需要收集的宝藏的距离场
+    treasure_field: DistanceField,
+    // 指南针箭头剩余的显示时间（秒）
+    hint_remaining: f64,
+    // 被抓到后剩余的保护时间（秒）
+    catch_grace: f64,
+    state: GameState,
+    // 最近一次 GameOver 的原因
+    game_over_reason: GameOverReason,
+    game_time: f64,
+    time_limit: f64,
+    // 每个难度各自的最佳时间
+    best_times: BTreeMap<Difficulty, f64>,
+    seed: u32,
+    generator: MapGenerator,
+    spawn: (usize, usize),
+    // 手工编辑的关卡，存在时 reset 会恢复该关卡而不是随机生成
+    level: Option<Level>,
+    tick_rate: f64,
+    // 还没有模拟的累积时间（秒）
+    accumulator: f64,
+    // 上一步模拟前玩家的位置，用于插值
+    previous_position: (f64, f64),
+    // 开场倒计时剩余的秒数
+    intro_remaining: f64,
+    // 还没有被前端取走的事件
+    events: Vec<GameEvent>,
+    // 战役进度，存在时地图、宝藏数量和时间限制都由当前关卡决定
+    campaign: Option<Campaign>,
+    // 进入当前关卡时已有的分数，重玩本关时恢复到这个分数
+    level_start_score: u32,
+    config: GameConfig,
+    difficulty: Difficulty,
+}
+
+impl Simulation {
+    pub fn new(width: u32, height: u32, seed: u32) -> Simulation {
+        Simulation::with_generator(width, height, seed, MapGenerator::default())
+    }
+
+    pub fn with_generator(width: u32, height: u32, seed: u32, generator: MapGenerator) -> Simulation {
+        Simulation::generated(width, height, seed, generator, GameConfig::default())
+    }
+
+    // 使用自定义规则创建，参数不合理时返回错误
+    pub fn with_config(width: u32, height: u32, seed: u32, generator: MapGenerator, config: GameConfig) -> Result<Simulation, ConfigError> {
+        config.validate_for(width, height)?;
+        Ok(Simulation::generated(width, height, seed, generator, config))
+    }
+
+    fn generated(width: u32, height: u32, seed: u32, generator: MapGenerator, config: GameConfig) -> Simulation {
+        let (map_width, map_height) = config.map_size(width, height);
+        let map = GameMap::from_tiles(map_width, map_height, config.tile_size, vec![TileType::Empty; map_width * map_height]);
+        // 指定了地图的格子数时，地图大小与给出的画布大小无关
+        let (world_width, world_height) = config.world_size(width, height);
+
+        let mut simulation = Simulation::empty(world_width, world_height, map, seed, generator, None, config);
+        simulation.canvas_size = (width, height);
+        simulation.build_world();
+        simulation
+    }
+
+    // 从手工编辑的关卡创建
+    pub fn from_level(level: Level) -> Simulation {
+        let tile_size = level.map.tile_size();
+        let width = (level.map.width() as f64 * tile_size) as u32;
+        let height = (level.map.height() as f64 * tile_size) as u32;
+        let map = level.map.clone();
+
+        let config = GameConfig { tile_size, ..GameConfig::default() };
+        let mut simulation = Simulation::empty(width, height, map, 0, MapGenerator::default(), Some(level), config);
+        simulation.build_world();
+        simulation
+    }
+
+    fn empty(width: u32, height: u32, map: GameMap, seed: u32, generator: MapGenerator, level: Option<Level>, config: GameConfig) -> Simulation {
+        let mut player = Player::new(0.0, 0.0);
+        player.set_movement(config.movement);
+        let mut treasure_manager = TreasureManager::new();
+        treasure_manager.set_treasure_value(config.treasure_value);
+        treasure_manager.set_combo_settings(config.combo_window, config.combo_decay);
+        let treasure_field = DistanceField::new(&map, &[]);
+
+        Simulation {
+            width,
+            height,
+            canvas_size: (width, height),
+            tile_size: map.tile_size(),
+            player,
+            map,
+            input: Input::default(),
+            treasure_manager,
+            guards: Vec::new(),
+            treasure_field,
+            hint_remaining: 0.0,
+            catch_grace: 0.0,
+            state: GameState::Menu,
+            game_over_reason: GameOverReason::default(),
+            game_time: 0.0,
+            time_limit: config.time_limit,
+            best_times: BTreeMap::new(),
+            seed,
+            generator,
+            spawn: SPAWN_TILE,
+            level,
+            tick_rate: DEFAULT_TICK_RATE,
+            accumulator: 0.0,
+            previous_position: (0.0, 0.0),
+            intro_remaining: 0.0,
+            events: Vec::new(),
+            campaign: None,
+            level_start_score: 0,
+            config,
+            difficulty: Difficulty::default(),
+        }
+    }
+
+    // 根据关卡或种子生成地图、宝藏和守卫，并把玩家放回出生点
+    fn build_world(&mut self) {
+        let mut rng = Rng::new(self.level_seed() as u64);
+        if let Some(level) = &self.level {
+            self.map = level.map.clone();
+            self.spawn = level.spawn;
+            let positions: Vec<(f64, f64)> = level.treasures.iter()
+                .map(|&(x, y)| ((x as f64 + 0.5) * self.tile_size, (y as f64 + 0.5) * self.tile_size))
+                .collect();
+            self.treasure_manager.place_treasures(&positions);
+        } else {
+            self.spawn = SPAWN_TILE;
+            self.map = generate_map(
+                self.map.width(),
+                self.map.height(),
+                self.tile_size,
+                self.generator,
+                self.difficulty.obstacle_density(self.config.obstacle_density),
+                &mut rng,
+            );
+            self.treasure_manager.generate_treasures(
+                self.round_treasure_count(),
+                &self.map,
+                SPAWN_TILE,
+                self.difficulty.treasure_min_spread(self.config.treasure_min_spread) * self.tile_size,
+                &mut rng,
+            );
+        }
+        self.rebuild_treasure_field();
+        // 守卫在宝藏之后生成，不影响同一种子的地图和宝藏布局
+        self.guards = guard::spawn_guards(
+            &self.map,
+            self.difficulty.guard_count(self.config.guard_count),
+            self.spawn,
+            self.config.guard_speed,
+            self.config.guard_view_range * self.tile_size,
+            &mut rng,
+        );
+
+        self.player.set_position(
+            (self.spawn.0 as f64 + 0.5) * self.tile_size,
+            (self.spawn.1 as f64 + 0.5) * self.tile_size,
+        );
+        self.player.set_velocity(0.0, 0.0);
+        self.update_visibility();
+        self.catch_grace = 0.0;
+        self.hint_remaining = 0.0;
+        self.previous_position = (self.player.x(), self.player.y());
+        self.accumulator = 0.0;
+    }
+
+    // 按还没收集的需要收集的宝藏重新计算距离场
+    fn rebuild_treasure_field(&mut self) {
+        let sources: Vec<Option<(usize, usize)>> = self.treasure_manager.treasures().iter()
+            .map(|treasure| {
+                (!treasure.is_collected() && treasure.kind().is_required())
+                    .then(|| self.map.tile_at(treasure.x(), treasure.y()))
+            })
+            .collect();
+        self.treasure_field = DistanceField::new(&self.map, &sources);
+    }
+
+    // 从菜单或结算画面开始新的一局，先进入开场倒计时
+    // 战役中过关后开始下一关并保留分数，通关或失败后从第一关重新开始
+    pub fn start(&mut self) -> bool {
+        if !self.state.can_transition_to(GameState::LevelIntro) {
+            return false;
+        }
+        if let Some(campaign) = &mut self.campaign {
+            match self.state {
+                GameState::Results if campaign.advance() => {
+                    self.level_start_score = self.treasure_manager.get_score();
+                }
+                GameState::Results | GameState::GameOver => {
+                    campaign.restart();
+                    self.level_start_score = 0;
+                }
+                _ => {}
+            }
+            self.apply_campaign_level();
+        }
+        self.transition(GameState::LevelIntro)
+    }
+
+    // 结束当前这一局并回到菜单
+    pub fn stop(&mut self) -> bool {
+        self.transition(GameState::Menu)
+    }
+
+    // 暂停游戏，已经过的游戏时间保持不变
+    pub fn pause(&mut self) -> bool {
+        self.transition(GameState::Paused)
+    }
+
+    // 从暂停处继续，计时从暂停时的游戏时间接着走
+    pub fn resume(&mut self) -> bool {
+        self.state == GameState::Paused && self.transition(GameState::Playing)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state == GameState::Paused
+    }
+
+    // 重置游戏，地图和宝藏按新种子重新生成（手工关卡则恢复原样）
+    // 重置后回到菜单，战役从第一关重新开始
+    pub fn reset(&mut self, seed: u32) {
+        self.transition(GameState::Menu);
+        self.seed = seed;
+        if let Some(campaign) = &mut self.campaign {
+            campaign.restart();
+        }
+        self.level_start_score = 0;
+        self.apply_campaign_level();
+        self.restart_round();
+    }
+
+    // 清空计时和按键，分数回到本关开始时的值，重新生成地图和宝藏，玩家回到出生点
+    fn restart_round(&mut self) {
+        self.game_time = 0.0;
+        self.time_limit = self.round_time_limit();
+        self.input = Input::default();
+        self.treasure_manager.set_score(self.level_start_score);
+        self.build_world();
+    }
+
+    // 开始战役，回到菜单并从第一关开始
+    pub fn set_campaign(&mut self, campaign: Campaign) {
+        self.campaign = Some(campaign);
+        self.reset(self.seed);
+    }
+
+    fn leave_campaign(&mut self) {
+        self.campaign = None;
+    }
+
+    // 本局的宝藏数量：来自战役当前关卡或 config，再按难度调整
+    fn round_treasure_count(&self) -> u32 {
+        let base = self.campaign.as_ref()
+            .map_or(self.config.treasure_count, |campaign| campaign.current().treasure_count);
+        self.difficulty.treasure_count(base)
+    }
+
+    // 本局的时间限制，来源同上
+    fn round_time_limit(&self) -> f64 {
+        let base = self.campaign.as_ref()
+            .map_or(self.config.time_limit, |campaign| campaign.current().time_limit);
+        self.difficulty.time_limit(base)
+    }
+
+    // 选择难度，只能在一局开始之前（菜单或结算画面）更换，换完后回到菜单并重新生成地图
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) -> bool {
+        if self.is_running() || self.is_paused() {
+            return false;
+        }
+        self.difficulty = difficulty;
+        self.reset(self.seed);
+        true
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    // 更换游戏规则并回到菜单。手工关卡保留自己的格子大小，新的格子大小和地图大小在随机地图上生效
+    pub fn set_config(&mut self, config: GameConfig) -> Result<(), ConfigError> {
+        let (canvas_width, canvas_height) = self.canvas_size;
+        if self.level.is_none() {
+            config.validate_for(canvas_width, canvas_height)?;
+        } else {
+            config.validate()?;
+        }
+
+        self.config = config;
+        self.player.set_movement(config.movement);
+        self.treasure_manager.set_treasure_value(config.treasure_value);
+        self.treasure_manager.set_combo_settings(config.combo_window, config.combo_decay);
+        let (map_width, map_height) = config.map_size(canvas_width, canvas_height);
+        let resized = (map_width, map_height) != (self.map.width(), self.map.height());
+        if self.level.is_none() && (config.tile_size != self.tile_size || resized) {
+            self.tile_size = config.tile_size;
+            self.set_map_size(map_width, map_height);
+        }
+        self.reset(self.seed);
+        Ok(())
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    // 按战役当前关卡设置地图来源、宝藏数量和时间限制
+    fn apply_campaign_level(&mut self) {
+        let Some(campaign) = &self.campaign else {
+            return;
+        };
+        match campaign.current().source.clone() {
+            LevelSource::Generated { generator, width, height } => {
+                self.generator = generator;
+                self.level = None;
+                self.set_map_size(width, height);
+            }
+            LevelSource::Authored(level) => {
+                self.tile_size = level.map.tile_size();
+                self.set_map_size(level.map.width(), level.map.height());
+                self.level = Some(level);
+            }
+        }
+    }
+
+    // 调整地图大小（格子数），地图内容在 build_world 时生成
+    fn set_map_size(&mut self, width: usize, height: usize) {
+        self.width = (width as f64 * self.tile_size) as u32;
+        self.height = (height as f64 * self.tile_size) as u32;
+        self.map = GameMap::from_tiles(width, height, self.tile_size, vec![TileType::Empty; width * height]);
+    }
+
+    // 战役中每一关使用不同的种子，同一个种子的战役总是同样的地图
+    fn level_seed(&self) -> u32 {
+        let index = self.campaign.as_ref().map_or(0, |campaign| campaign.current_index());
+        self.seed.wrapping_add(index as u32)
+    }
+
+    // 切换状态：依次调用离开旧状态和进入新状态的处理，并记录 StateChanged 事件
+    // 状态机不允许的切换会被忽略，返回是否切换成功
+    fn transition(&mut self, next: GameState) -> bool {
+        if !self.state.can_transition_to(next) {
+            return false;
+        }
+        let previous = self.state;
+        self.on_exit(previous);
+        self.state = next;
+        self.on_enter(next);
+        self.events.push(GameEvent::StateChanged { from: previous, to: next });
+        true
+    }
+
+    fn on_exit(&mut self, state: GameState) {
+        match state {
+            GameState::LevelIntro => self.intro_remaining = 0.0,
+            // 离开游戏状态时松开所有按键，继续或重新开始时不会带着旧的输入
+            GameState::Playing => self.input = Input::default(),
+            GameState::Menu | GameState::Paused | GameState::Results | GameState::GameOver => {}
+        }
+    }
+
+    fn on_enter(&mut self, state: GameState) {
+        match state {
+            GameState::Menu => {
+                self.input = Input::default();
+                self.accumulator = 0.0;
+            }
+            // 每次开场都是全新的一局，同一种子或关卡得到同样的地图
+            GameState::LevelIntro => {
+                self.restart_round();
+                self.intro_remaining = INTRO_DURATION;
+            }
+            GameState::Results => {
+                // 更新当前难度的最佳时间
+                let best_time = self.best_times.entry(self.difficulty).or_insert(self.game_time);
+                if self.game_time < *best_time {
+                    *best_time = self.game_time;
+                }
+            }
+            GameState::Playing | GameState::Paused | GameState::GameOver => {}
+        }
+    }
+
+    // 载入手工编辑的关卡，替换当前地图
+    pub fn load_level(&mut self, level: Level) {
+        self.leave_campaign();
+        let tile_size = level.map.tile_size();
+        self.width = (level.map.width() as f64 * tile_size) as u32;
+        self.height = (level.map.height() as f64 * tile_size) as u32;
+        self.tile_size = tile_size;
+        self.level = Some(level);
+        self.reset(self.seed);
+    }
+
+    // 保存当前游戏的完整快照
+    pub fn save_state(&self) -> SaveState {
+        SaveState {
+            version: SAVE_VERSION,
+            seed: self.seed,
+            generator: self.generator,
+            level: self.level.clone(),
+            map: self.map.clone(),
+            spawn: self.spawn,
+            player: self.player,
+            treasures: self.treasure_manager.treasures().to_vec(),
+            guards: self.guards.clone(),
+            catch_grace: self.catch_grace,
+            hint_remaining: self.hint_remaining,
+            score: self.treasure_manager.get_score(),
+            combo: *self.treasure_manager.combo(),
+            game_time: self.game_time,
+            time_limit: self.time_limit,
+            best_time: self.best_time(),
+            best_times: self.best_times.clone(),
+            difficulty: self.difficulty,
+            state: self.state,
+            game_over_reason: self.game_over_reason,
+            is_running: self.is_running(),
+            intro_remaining: self.intro_remaining,
+            campaign: self.campaign.clone(),
+            level_start_score: self.level_start_score,
+            config: self.config,
+        }
+    }
+
+    // 从快照恢复，游戏时间、分数和运行状态都与保存时一致
+    pub fn restore(&mut self, save: SaveState) -> Result<(), SaveError> {
+        save.validate()?;
+
+        self.tile_size = save.map.tile_size();
+        self.width = (save.map.width() as f64 * self.tile_size) as u32;
+        self.height = (save.map.height() as f64 * self.tile_size) as u32;
+        self.seed = save.seed;
+        self.generator = save.generator;
+        self.level = save.level;
+        self.map = save.map;
+        self.spawn = save.spawn;
+        self.player = save.player;
+        self.previous_position = (self.player.x(), self.player.y());
+        self.accumulator = 0.0;
+        self.treasure_manager.restore(save.treasures, save.score, &save.combo);
+        self.guards = save.guards;
+        self.catch_grace = save.catch_grace;
+        self.hint_remaining = save.hint_remaining;
+        self.rebuild_treasure_field();
+        self.update_visibility();
+        self.game_time = save.game_time;
+        self.time_limit = save.time_limit;
+        self.state = save.state;
+        self.game_over_reason = save.game_over_reason;
+        self.intro_remaining = save.intro_remaining;
+        self.campaign = save.campaign;
+        self.level_start_score = save.level_start_score;
+        self.config = save.config;
+        self.treasure_manager.set_treasure_value(self.config.treasure_value);
+        self.treasure_manager.set_combo_settings(self.config.combo_window, self.config.combo_decay);
+        self.difficulty = save.difficulty;
+        self.best_times = save.best_times;
+        // 旧存档只有一个最佳时间，记在存档的难度下
+        if let Some(best_time) = save.best_time {
+            self.best_times.entry(self.difficulty).or_insert(best_time);
+        }
+        self.input = Input::default();
+        self.events.clear();
+        Ok(())
+    }
+
+    // 把当前地图、出生点和宝藏导出为关卡
+    pub fn to_level(&self) -> Level {
+        let treasures = self.treasure_manager.treasures().iter()
+            .map(|treasure| self.map.tile_at(treasure.x(), treasure.y()))
+            .collect();
+        let mut map = self.map.clone();
+        map.clear_visibility();
+        Level {
+            map,
+            spawn: self.spawn,
+            treasures,
+        }
+    }
+
+    // 推进 delta_time 秒，返回上次取走事件之后发生的所有事件
+    // 时间先累积起来，再按固定步长逐步模拟，这样移动速度与显示器刷新率无关
+    pub fn update(&mut self, delta_time: f64) -> Vec<GameEvent> {
+        if !self.is_running() {
+            return self.take_events();
+        }
+
+        // 防止 NaN 或过长的帧（例如切换标签页后）导致一次模拟太多步
+        let delta_time = if delta_time.is_nan() { 0.0 } else { delta_time.clamp(0.0, MAX_FRAME_TIME) };
+        self.accumulator += delta_time;
+
+        let step = 1.0 / self.tick_rate;
+        while self.accumulator >= step && self.is_running() {
+            self.accumulator -= step;
+            self.tick(step);
+        }
+
+        self.take_events()
+    }
+
+    // 取走 start、pause 等调用以及模拟过程中产生的事件
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    // 一个固定步长的模拟
+    fn tick(&mut self, step: f64) {
+        self.previous_position = (self.player.x(), self.player.y());
+
+        // 开场倒计时期间玩家不能移动，游戏时间也不走
+        if self.state == GameState::LevelIntro {
+            self.intro_remaining -= step;
+            if self.intro_remaining <= 0.0 {
+                self.transition(GameState::Playing);
+            }
+            return;
+        }
+
+        // 防止游戏时间出现 NaN
+        if !self.game_time.is_nan() {
+            self.game_time += step;
+        } else {
+            self.game_time = 0.0;
+        }
+
+        // 先检查时间限制
+        if self.game_time >= self.time_limit {
+            self.game_over(GameOverReason::TimeUp);
+            return;
+        }
+
+        self.treasure_manager.update_combo(step);
+        self.hint_remaining = (self.hint_remaining - step).max(0.0);
+
+        // 更新玩家位置和收集宝藏，沙漏延长时间限制，指南针显示方向箭头
+        for collected in self.update_player(step) {
+            self.time_limit += collected.kind.time_bonus();
+            self.hint_remaining = self.hint_remaining.max(collected.kind.hint_duration());
+            self.treasure_field.remove_source(&self.map, collected.index);
+            self.events.push(GameEvent::TreasureCollected {
+                kind: collected.kind,
+                points: collected.points,
+                chain: collected.chain,
+            });
+        }
+
+        self.update_visibility();
+
+        // 检查胜利条件
+        if self.treasure_manager.all_treasures_collected() {
+            self.transition(GameState::Results);
+            return;
+        }
+
+        self.update_guards(step);
+    }
+
+    // 移动守卫，并处理玩家被抓到的情况
+    fn update_guards(&mut self, step: f64) {
+        let (x, y, size) = (self.player.x(), self.player.y(), self.player.size());
+        let target = self.player_center();
+        let mut caught = false;
+        self.catch_grace = (self.catch_grace - step).max(0.0);
+        for guard in &mut self.guards {
+            if guard.update(&self.map, target, step) {
+                self.events.push(GameEvent::GuardAlerted);
+            }
+            // 同时碰到玩家的守卫都停下，只算被抓到一次
+            if self.catch_grace == 0.0 && !guard.is_stunned() && guard.touches(x, y, size) {
+                guard.stun();
+                caught = true;
+            }
+        }
+        if !caught {
+            return;
+        }
+
+        self.catch_grace = CATCH_GRACE;
+        let contact = self.config.guard_contact;
+        self.events.push(GameEvent::PlayerCaught { contact });
+        match contact {
+            GuardContact::Penalty(seconds) => {
+                self.time_limit -= seconds;
+                if self.game_time >= self.time_limit {
+                    self.game_over(GameOverReason::TimeUp);
+                }
+            }
+            GuardContact::EndRound => {
+                self.game_over(GameOverReason::Caught);
+            }
+        }
+    }
+
+    fn game_over(&mut self, reason: GameOverReason) {
+        if self.transition(GameState::GameOver) {
+            self.game_over_reason = reason;
+        }
+    }
+
+    // 设置每秒模拟的步数，超出范围时取最近的边界值，NaN 被忽略
+    pub fn set_tick_rate(&mut self, tick_rate: f64) {
+        if !tick_rate.is_nan() {
+            self.tick_rate = tick_rate.clamp(MIN_TICK_RATE, MAX_TICK_RATE);
+        }
+    }
+
+    pub fn tick_rate(&self) -> f64 {
+        self.tick_rate
+    }
+
+    // 上一步模拟之后累积但还没模拟的时间占一步的比例（0 到 1），用于平滑绘制
+    pub fn interpolation_alpha(&self) -> f64 {
+        (self.accumulator * self.tick_rate).clamp(0.0, 1.0)
+    }
+
+    // 按 alpha 在上一步和当前位置之间插值得到的玩家位置
+    pub fn interpolated_player_position(&self, alpha: f64) -> (f64, f64) {
+        let (previous_x, previous_y) = self.previous_position;
+        (
+            previous_x + (self.player.x() - previous_x) * alpha,
+            previous_y + (self.player.y() - previous_y) * alpha,
+        )
+    }
+
+    // 移动玩家，返回这一步收集的宝藏
+    fn update_player(&mut self, step: f64) -> Vec<Collected> {
+        let (dir_x, dir_y) = self.input.direction();
+        self.player.accelerate(dir_x, dir_y, step);
+
+        // 碰撞检测，X 和 Y 分开处理以便沿墙滑动
+        self.player.move_and_slide(&self.map, step);
+
+        self.treasure_manager.check_collection(
+            self.player.x(),
+            self.player.y(),
+            self.player.size()
+        )
+    }
+
+    // 调整玩家的加速度、最大速度和摩擦力
+    pub fn set_player_movement(&mut self, movement: Movement) {
+        self.config.movement = movement;
+        self.player.set_movement(movement);
+    }
+
+    // 更换地图生成方式，下一次 reset 时生效（不再使用手工关卡）
+    pub fn set_map_generator(&mut self, generator: MapGenerator) {
+        self.leave_campaign();
+        self.generator = generator;
+        self.level = None;
+    }
+
+    pub fn map_generator(&self) -> MapGenerator {
+        self.generator
+    }
+
+    pub fn set_input(&mut self, input: Input) {
+        self.input = input;
+    }
+
+    pub fn input(&self) -> Input {
+        self.input
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // 开场倒计时或游戏中，需要每帧调用 update
+    pub fn is_running(&self) -> bool {
+        self.state.is_active()
+    }
+
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    // 最近一次 GameOver 的原因，只在 GameOver 状态下有意义
+    pub fn game_over_reason(&self) -> GameOverReason {
+        self.game_over_reason
+    }
+
+    pub fn intro_remaining(&self) -> f64 {
+        self.intro_remaining.max(0.0)
+    }
+
+    pub fn game_time(&self) -> f64 {
+        self.game_time
+    }
+
+    pub fn time_limit(&self) -> f64 {
+        self.time_limit
+    }
+
+    pub fn remaining_time(&self) -> f64 {
+        (self.time_limit - self.game_time).max(0.0)
+    }
+
+    // 当前难度的最佳时间
+    pub fn best_time(&self) -> Option<f64> {
+        self.best_time_for(self.difficulty)
+    }
+
+    pub fn best_time_for(&self, difficulty: Difficulty) -> Option<f64> {
+        self.best_times.get(&difficulty).copied()
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn spawn(&self) -> (usize, usize) {
+        self.spawn
+    }
+
+    pub fn player(&self) -> &Player {
+        &self.player
+    }
+
+    pub fn map(&self) -> &GameMap {
+        &self.map
+    }
+
+    pub fn treasures(&self) -> &TreasureManager {
+        &self.treasure_manager
+    }
+
+    pub fn guards(&self) -> &[Guard] {
+        &self.guards
+    }
+
+    pub fn treasure_field(&self) -> &DistanceField {
+        &self.treasure_field
+    }
+
+    // 玩家到最近的需要收集的宝藏还要走多远（格子数）
+    pub fn treasure_distance(&self) -> Option<f64> {
+        let (x, y) = self.player_center();
+        let (tile_x, tile_y) = self.map.tile_at(x, y);
+        self.treasure_field.distance(tile_x, tile_y)
+    }
+
+    pub fn hint_remaining(&self) -> f64 {
+        self.hint_remaining
+    }
+
+    // 指南针生效时从玩家指向最近宝藏的方向（单位向量）
+    pub fn hint_direction(&self) -> Option<(f64, f64)> {
+        if self.hint_remaining <= 0.0 {
+            return None;
+        }
+        let (x, y) = self.player_center();
+        self.treasure_field.direction(&self.map, x, y)
+    }
+
+    // 按玩家当前所在的格子重新计算视野，看到的格子记为已探索
+    fn update_visibility(&mut self) {
+        let (x, y) = self.player_center();
+        let (tile_x, tile_y) = self.map.tile_at(x, y);
+        self.map.reveal_from(tile_x, tile_y, self.config.view_radius);
+    }
+
+    // 已探索的空地占全部空地的比例（0 到 1）
+    pub fn exploration(&self) -> f64 {
+        self.map.exploration()
+    }
+
+    fn player_center(&self) -> (f64, f64) {
+        let half = self.player.size() / 2.0;
+        (self.player.x() + half, self.player.y() + half)
+    }
+
+    // 被抓到后剩余的保护时间，期间不会再次被抓到
+    pub fn catch_grace(&self) -> f64 {
+        self.catch_grace
+    }
+
+    pub fn score(&self) -> u32 {
+        self.treasure_manager.get_score()
+    }
+
+    pub fn campaign(&self) -> Option<&Campaign> {
+        self.campaign.as_ref()
+    }
+
+    // 当前关卡序号（从 0 开始），不在战役中时为 0
+    pub fn current_level(&self) -> usize {
+        self.campaign.as_ref().map_or(0, |campaign| campaign.current_index())
+    }
+
+    // 关卡总数，不在战役中时为 1
+    pub fn level_count(&self) -> usize {
+        self.campaign.as_ref().map_or(1, |campaign| campaign.level_count())
+    }
+
+    pub fn has_next_level(&self) -> bool {
+        self.campaign.as_ref().is_some_and(|campaign| campaign.has_next())
+    }
+}
+
+// 生成地图，并保证出生点是空地、所有空地都能从出生点到达
+fn generate_map(width: usize, height: usize, tile_size: f64, generator: MapGenerator, obstacle_density: f64, rng: &mut Rng) -> GameMap {
+    let mut map = GameMap::new(width, height, tile_size, generator, obstacle_density, rng);
+    map.set_tile(SPAWN_TILE.0, SPAWN_TILE.1, TileType::Empty);
+    map.connect_regions(SPAWN_TILE.0, SPAWN_TILE.1);
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f64 = 1.0 / 60.0;
+
+    // 出生点右边隔一格就是宝藏的小关卡
+    const CORRIDOR: &str = "\
+#######
+#P..T.#
+#######
+";
+
+    // 没有守卫的关卡，避免守卫影响脚本化的输入
+    fn corridor(time_limit: f64) -> Simulation {
+        let level = Level::parse(CORRIDOR, 40.0).unwrap();
+        let mut simulation = Simulation::from_level(level);
+        let config = GameConfig { guard_count: 0, time_limit, ..*simulation.config() };
+        simulation.set_config(config).unwrap();
+        simulation
+    }
+
+    // 按 60 帧每秒推进 seconds 秒或直到 stop 返回 true，返回期间的所有事件
+    fn run_until(simulation: &mut Simulation, seconds: f64, stop: impl Fn(&Simulation) -> bool) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        let mut elapsed = 0.0;
+        while elapsed < seconds && !stop(simulation) {
+            events.extend(simulation.update(FRAME));
+            elapsed += FRAME;
+        }
+        events
+    }
+
+    fn treasure_positions(simulation: &Simulation) -> Vec<(f64, f64)> {
+        simulation.treasures().treasures().iter().map(|t| (t.x(), t.y())).collect()
+    }
+
+    #[test]
+    fn same_seed_gives_same_map_and_treasures() {
+        let first = Simulation::new(800, 600, 42);
+        let second = Simulation::new(800, 600, 42);
+        assert_eq!(first.to_level().to_level_string(), second.to_level().to_level_string());
+        assert_eq!(treasure_positions(&first), treasure_positions(&second));
+
+        let other = Simulation::new(800, 600, 43);
+        assert_ne!(first.to_level().to_level_string(), other.to_level().to_level_string());
+    }
+
+    #[test]
+    fn scripted_input_collects_treasure_and_wins() {
+        let mut simulation = corridor(60.0);
+        assert!(simulation.start());
+        run_until(&mut simulation, INTRO_DURATION + 1.0, |s| s.state() == GameState::Playing);
+        assert_eq!(simulation.state(), GameState::Playing);
+
+        simulation.set_input(Input { right: true, ..Input::default() });
+        let events = run_until(&mut simulation, 10.0, |s| s.state() != GameState::Playing);
+
+        assert_eq!(simulation.state(), GameState::Results);
+        assert!(events.iter().any(|event| matches!(event, GameEvent::TreasureCollected { kind: TreasureKind::Coin, .. })));
+        assert!(events.contains(&GameEvent::StateChanged { from: GameState::Playing, to: GameState::Results }));
+        assert_eq!(simulation.score(), 10);
+        assert_eq!(simulation.best_time(), Some(simulation.game_time()));
+    }
+
+    #[test]
+    fn intro_countdown_becomes_playing() {
+        let mut simulation = corridor(60.0);
+        assert!(simulation.start());
+        assert_eq!(simulation.state(), GameState::LevelIntro);
+        assert_eq!(simulation.intro_remaining(), INTRO_DURATION);
+        assert_eq!(simulation.take_events(), vec![GameEvent::StateChanged { from: GameState::Menu, to: GameState::LevelIntro }]);
+
+        // 倒计时期间按键不会移动玩家，游戏时间也不走
+        let spawn = (simulation.player().x(), simulation.player().y());
+        simulation.set_input(Input { right: true, ..Input::default() });
+        run_until(&mut simulation, INTRO_DURATION - 0.5, |_| false);
+        assert_eq!(simulation.state(), GameState::LevelIntro);
+        assert_eq!((simulation.player().x(), simulation.player().y()), spawn);
+        assert_eq!(simulation.game_time(), 0.0);
+
+        let events = run_until(&mut simulation, 1.0, |s| s.state() == GameState::Playing);
+        assert_eq!(simulation.state(), GameState::Playing);
+        assert_eq!(simulation.intro_remaining(), 0.0);
+        assert_eq!(events, vec![GameEvent::StateChanged { from: GameState::LevelIntro, to: GameState::Playing }]);
+    }
+
+    #[test]
+    fn pause_keeps_game_time_and_releases_input() {
+        let mut simulation = corridor(60.0);
+        simulation.start();
+        run_until(&mut simulation, INTRO_DURATION + 1.0, |s| s.state() == GameState::Playing);
+        run_until(&mut simulation, 0.5, |_| false);
+        simulation.set_input(Input { down: true, ..Input::default() });
+
+        assert!(simulation.pause());
+        assert_eq!(simulation.input(), Input::default());
+        let game_time = simulation.game_time();
+        run_until(&mut simulation, 2.0, |_| false);
+        assert_eq!(simulation.game_time(), game_time);
+
+        assert!(!simulation.start());
+        assert!(simulation.resume());
+        assert_eq!(simulation.state(), GameState::Playing);
+        run_until(&mut simulation, 0.5, |_| false);
+        assert!(simulation.game_time() > game_time);
+    }
+
+    #[test]
+    fn invalid_transitions_are_ignored() {
+        let mut simulation = corridor(60.0);
+        assert!(!simulation.pause());
+        assert!(!simulation.resume());
+        assert!(!simulation.stop());
+        assert_eq!(simulation.state(), GameState::Menu);
+        assert!(simulation.take_events().is_empty());
+
+        simulation.start();
+        assert!(!simulation.pause());
+        assert!(simulation.stop());
+        assert_eq!(simulation.state(), GameState::Menu);
+        assert_eq!(simulation.intro_remaining(), 0.0);
+    }
+
+    #[test]
+    fn restarting_after_results_rebuilds_the_round() {
+        let mut simulation = corridor(60.0);
+        simulation.start();
+        run_until(&mut simulation, INTRO_DURATION + 1.0, |s| s.state() == GameState::Playing);
+        simulation.set_input(Input { right: true, ..Input::default() });
+        run_until(&mut simulation, 10.0, |s| s.state() != GameState::Playing);
+        assert_eq!(simulation.state(), GameState::Results);
+
+        assert!(simulation.start());
+        assert_eq!(simulation.state(), GameState::LevelIntro);
+        assert_eq!(simulation.game_time(), 0.0);
+        assert_eq!(simulation.score(), 0);
+        assert!(!simulation.treasures().is_treasure_collected(0));
+        assert_eq!(simulation.input(), Input::default());
+    }
+
+    #[test]
+    fn save_in_the_middle_of_a_combo_restores_exactly() {
+        let level = Level::parse("#############\n#P.T.T.....T#\n#############\n", 40.0).unwrap();
+        let mut simulation = Simulation::from_level(level);
+        let config = GameConfig { guard_count: 0, ..*simulation.config() };
+        simulation.set_config(config).unwrap();
+        simulation.start();
+        run_until(&mut simulation, INTRO_DURATION + 1.0, |s| s.state() == GameState::Playing);
+        simulation.set_input(Input { right: true, ..Input::default() });
+        run_until(&mut simulation, 5.0, |s| s.treasures().combo().chain() == 2);
+        assert_eq!(simulation.treasures().combo().multiplier(), 1.5);
+
+        let json = simulation.save_state().to_json();
+        let mut restored = corridor(60.0);
+        restored.restore(SaveState::from_json(&json).unwrap()).unwrap();
+        let (saved, loaded) = (simulation.treasures().combo(), restored.treasures().combo());
+        assert_eq!(loaded.multiplier(), saved.multiplier());
+        assert_eq!(loaded.chain(), saved.chain());
+        assert_eq!(loaded.remaining(), saved.remaining());
+        assert_eq!(restored.score(), simulation.score());
+        assert_eq!(restored.save_state().to_json(), json);
+    }
+
+    #[test]
+    fn restore_rejects_broken_guards() {
+        let simulation = Simulation::new(800, 600, 7);
+        assert!(!simulation.guards().is_empty());
+        let save: serde_json::Value = serde_json::from_str(&simulation.save_state().to_json()).unwrap();
+
+        let broken = [
+            ("route", serde_json::json!([])),
+            ("route_index", serde_json::json!(1000)),
+            ("route", serde_json::json!([[1, 1], [500, 1]])),
+        ];
+        for (field, value) in broken {
+            let mut save = save.clone();
+            save["guards"][0]["route_index"] = serde_json::json!(0);
+            save["guards"][0][field] = value;
+            let save: SaveState = serde_json::from_value(save).unwrap();
+            let mut restored = Simulation::new(800, 600, 7);
+            assert_eq!(restored.restore(save).err(), Some(SaveError::InvalidGuard), "{}", field);
+        }
+    }
+
+    #[test]
+    fn restores_version_1_saves() {
+        let simulation = Simulation::new(800, 600, 7);
+        let mut save: serde_json::Value = serde_json::from_str(&simulation.save_state().to_json()).unwrap();
+        // 版本 1 的玩家只有固定的 speed
+        save["version"] = serde_json::json!(1);
+        let player = save["player"].as_object_mut().unwrap();
+        player.remove("velocity");
+        player.remove("movement");
+        player.insert("speed".to_string(), serde_json::json!(300.0));
+
+        let mut restored = Simulation::new(800, 600, 1);
+        restored.restore(SaveState::from_json(&save.to_string()).unwrap()).unwrap();
+        assert_eq!(restored.seed(), 7);
+        assert_eq!(restored.player().movement(), Movement::default());
+        assert_eq!(restored.player().velocity().x, 0.0);
+        assert_eq!(restored.save_state().version, SAVE_VERSION);
+    }
+
+    #[test]
+    fn tick_rate_is_clamped() {
+        let mut simulation = corridor(60.0);
+        simulation.set_tick_rate(1e9);
+        assert_eq!(simulation.tick_rate(), MAX_TICK_RATE);
+        simulation.set_tick_rate(0.0);
+        assert_eq!(simulation.tick_rate(), MIN_TICK_RATE);
+        simulation.set_tick_rate(f64::NAN);
+        assert_eq!(simulation.tick_rate(), MIN_TICK_RATE);
+        simulation.set_tick_rate(120.0);
+        assert_eq!(simulation.tick_rate(), 120.0);
+    }
+
+    #[test]
+    fn timer_running_out_ends_in_game_over() {
+        let mut simulation = corridor(1.0);
+        simulation.start();
+        let events = run_until(&mut simulation, INTRO_DURATION + 5.0, |s| s.state().is_finished());
+
+        assert_eq!(simulation.state(), GameState::GameOver);
+        assert_eq!(simulation.game_over_reason(), GameOverReason::TimeUp);
+        assert!(events.contains(&GameEvent::StateChanged { from: GameState::Playing, to: GameState::GameOver }));
+        assert_eq!(simulation.remaining_time(), 0.0);
+        assert_eq!(simulation.score(), 0);
+    }
+}
@@ -0,0 +1,113 @@
+// src/state.rs
+// 游戏流程的状态机：标题菜单 -> 关卡开场倒计时 -> 游戏中（可暂停）-> 结算或失败
+// 合法的状态切换集中写在 can_transition_to 里，进入/离开状态时的处理由 Simulation 完成
+use serde::{Deserialize, Serialize};
+
+// 关卡开始前倒计时的秒数
+pub const INTRO_DURATION: f64 = 3.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum GameState {
+    // 标题菜单，地图已经生成但还没开始
+    Menu,
+    // 关卡开场倒计时，计时和移动都还没开始
+    LevelIntro,
+    Playing,
+    Paused,
+    // 收集完所有宝藏（旧存档中叫 Won）
+    #[serde(alias = "Won")]
+    Results,
+    // 时间用完（旧存档中叫 TimeUp）
+    #[serde(alias = "TimeUp")]
+    GameOver,
+}
+
+// 一局以 GameOver 结束的原因
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum GameOverReason {
+    // 时间用完（包括被抓到扣时间后用完）
+    #[default]
+    TimeUp,
+    // 被守卫抓到直接结束
+    Caught,
+}
+
+impl GameOverReason {
+    pub fn name(self) -> &'static str {
+        match self {
+            GameOverReason::TimeUp => "time_up",
+            GameOverReason::Caught => "caught",
+        }
+    }
+}
+
+impl GameState {
+    // 是否允许从当前状态切换到 next，任何状态都可以回到菜单
+    pub fn can_transition_to(self, next: GameState) -> bool {
+        use GameState::*;
+        match (self, next) {
+            (current, Menu) => current != Menu,
+            (Menu, LevelIntro) | (Results, LevelIntro) | (GameOver, LevelIntro) => true,
+            (LevelIntro, Playing) => true,
+            (Playing, Paused) | (Playing, Results) | (Playing, GameOver) => true,
+            (Paused, Playing) => true,
+            _ => false,
+        }
+    }
+
+    // 这些状态下每帧都需要推进模拟
+    pub fn is_active(self) -> bool {
+        matches!(self, GameState::LevelIntro | GameState::Playing)
+    }
+
+    // 一局已经结束
+    pub fn is_finished(self) -> bool {
+        matches!(self, GameState::Results | GameState::GameOver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use GameState::*;
+
+    const ALL: [GameState; 6] = [Menu, LevelIntro, Playing, Paused, Results, GameOver];
+
+    #[test]
+    fn transition_table() {
+        let allowed = [
+            (Menu, LevelIntro),
+            (LevelIntro, Menu),
+            (LevelIntro, Playing),
+            (Playing, Menu),
+            (Playing, Paused),
+            (Playing, Results),
+            (Playing, GameOver),
+            (Paused, Menu),
+            (Paused, Playing),
+            (Results, Menu),
+            (Results, LevelIntro),
+            (GameOver, Menu),
+            (GameOver, LevelIntro),
+        ];
+        for from in ALL {
+            for to in ALL {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    allowed.contains(&(from, to)),
+                    "{:?} -> {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn active_and_finished_states() {
+        let active: Vec<GameState> = ALL.into_iter().filter(|state| state.is_active()).collect();
+        assert_eq!(active, vec![LevelIntro, Playing]);
+        let finished: Vec<GameState> = ALL.into_iter().filter(|state| state.is_finished()).collect();
+        assert_eq!(finished, vec![Results, GameOver]);
+    }
+}
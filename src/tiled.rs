@@ -0,0 +1,381 @@
+// src/tiled.rs
+// 导入 Tiled 编辑器的 JSON 地图（.tmj）
+// 图块层决定格子类型：图块的 class（旧版本为 type）或名为 "tile" 的自定义属性取值 wall / obstacle / empty，
+// 空图块（gid 为 0）视为空地。对象层中 class（或名字）为 spawn / treasure 的对象是出生点和宝藏。
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::config::MAX_MAP_TILES;
+use crate::level::{Level, LevelError};
+use crate::map::{GameMap, TileType};
+
+// gid 的最高几位是翻转标记，与图块编号无关
+const GID_FLAGS_MASK: u32 = 0xF000_0000;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TiledError {
+    Json(String),
+    Unsupported(String),
+    MapTooLarge { width: usize, height: usize },
+    LayerSizeMismatch { layer: String },
+    UnknownTileId { gid: u32, x: usize, y: usize },
+    UnmappedTile { gid: u32, value: String },
+    ObjectOutOfBounds { name: String, x: f64, y: f64 },
+    MissingSpawn,
+    DuplicateSpawn,
+    NoTreasures,
+    InvalidLevel(LevelError),
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TiledError::Json(message) => write!(f, "invalid Tiled map: {}", message),
+            TiledError::Unsupported(feature) => write!(f, "unsupported Tiled feature: {}", feature),
+            TiledError::MapTooLarge { width, height } => {
+                write!(f, "map is {}x{} tiles, at most {}x{} are allowed", width, height, MAX_MAP_TILES, MAX_MAP_TILES)
+            }
+            TiledError::LayerSizeMismatch { layer } => {
+                write!(f, "layer '{}' does not match the map size", layer)
+            }
+            TiledError::UnknownTileId { gid, x, y } => {
+                write!(f, "unknown tile id {} at ({}, {})", gid, x, y)
+            }
+            TiledError::UnmappedTile { gid, value } => {
+                write!(f, "tile id {} has unknown tile type '{}'", gid, value)
+            }
+            TiledError::ObjectOutOfBounds { name, x, y } => {
+                write!(f, "object '{}' at ({}, {}) is outside the map", name, x, y)
+            }
+            TiledError::MissingSpawn => write!(f, "map has no spawn object"),
+            TiledError::DuplicateSpawn => write!(f, "map has more than one spawn object"),
+            TiledError::NoTreasures => write!(f, "map has no treasure objects"),
+            TiledError::InvalidLevel(error) => write!(f, "invalid level: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for TiledError {}
+
+impl From<serde_json::Error> for TiledError {
+    fn from(error: serde_json::Error) -> Self {
+        TiledError::Json(error.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct TiledMap {
+    width: usize,
+    height: usize,
+    tilewidth: f64,
+    tileheight: f64,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default = "default_orientation")]
+    orientation: String,
+    layers: Vec<TiledLayer>,
+    #[serde(default)]
+    tilesets: Vec<TiledTileset>,
+}
+
+fn default_orientation() -> String {
+    "orthogonal".to_string()
+}
+
+#[derive(Deserialize)]
+struct TiledLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    data: Option<serde_json::Value>,
+    #[serde(default)]
+    encoding: Option<String>,
+    #[serde(default)]
+    objects: Vec<TiledObject>,
+}
+
+#[derive(Deserialize)]
+struct TiledTileset {
+    #[serde(default)]
+    firstgid: u32,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    tilecount: u32,
+    #[serde(default)]
+    tiles: Vec<TiledTile>,
+}
+
+#[derive(Deserialize)]
+struct TiledTile {
+    id: u32,
+    #[serde(default, alias = "type")]
+    class: String,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Deserialize)]
+struct TiledProperty {
+    name: String,
+    value: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct TiledObject {
+    #[serde(default)]
+    name: String,
+    #[serde(default, alias = "type")]
+    class: String,
+    x: f64,
+    y: f64,
+    #[serde(default)]
+    width: f64,
+    #[serde(default)]
+    height: f64,
+    #[serde(default)]
+    gid: Option<u32>,
+}
+
+// 把 Tiled JSON 地图转换为关卡，格子大小使用游戏自己的 tile_size
+pub fn import_tiled_json(text: &str, tile_size: f64) -> Result<Level, TiledError> {
+    let tiled: TiledMap = serde_json::from_str(text)?;
+
+    if tiled.infinite {
+        return Err(TiledError::Unsupported("infinite maps".to_string()));
+    }
+    if tiled.orientation != "orthogonal" {
+        return Err(TiledError::Unsupported(format!("{} orientation", tiled.orientation)));
+    }
+    if tiled.tilesets.iter().any(|tileset| tileset.source.is_some()) {
+        return Err(TiledError::Unsupported("external tilesets".to_string()));
+    }
+
+    // 宽高来自文件，分配格子之前先检查
+    let (width, height) = (tiled.width, tiled.height);
+    let too_large = TiledError::MapTooLarge { width, height };
+    if width > MAX_MAP_TILES || height > MAX_MAP_TILES {
+        return Err(too_large);
+    }
+    let size = width.checked_mul(height).ok_or(too_large)?;
+    let mut tiles = vec![TileType::Empty; size];
+    let mut spawn = None;
+    let mut treasures = Vec::new();
+
+    for layer in &tiled.layers {
+        match layer.kind.as_str() {
+            "tilelayer" => {
+                if layer.encoding.as_deref().is_some_and(|encoding| encoding != "csv") {
+                    return Err(TiledError::Unsupported(format!("encoded layer '{}'", layer.name)));
+                }
+                let data: Vec<u32> = match &layer.data {
+                    Some(data) => serde_json::from_value(data.clone())?,
+                    None => return Err(TiledError::Unsupported(format!("chunked layer '{}'", layer.name))),
+                };
+                if data.len() != size {
+                    return Err(TiledError::LayerSizeMismatch { layer: layer.name.clone() });
+                }
+
+                // 后面的图层覆盖前面的图层，空图块不覆盖
+                for (index, &raw_gid) in data.iter().enumerate() {
+                    let gid = raw_gid & !GID_FLAGS_MASK;
+                    if gid == 0 {
+                        continue;
+                    }
+                    tiles[index] = tile_type_for(&tiled.tilesets, gid, index % width, index / width)?;
+                }
+            }
+            "objectgroup" => {
+                for object in &layer.objects {
+                    let kind = if object.class.is_empty() { &object.name } else { &object.class };
+                    let kind = kind.to_lowercase();
+                    if kind != "spawn" && kind != "treasure" {
+                        continue;
+                    }
+
+                    // 图块对象的 y 是底边，其它对象的 y 是顶边，统一取中心点
+                    let center_x = object.x + object.width / 2.0;
+                    let center_y = if object.gid.is_some() {
+                        object.y - object.height / 2.0
+                    } else {
+                        object.y + object.height / 2.0
+                    };
+                    let tile_x = (center_x / tiled.tilewidth).floor();
+                    let tile_y = (center_y / tiled.tileheight).floor();
+                    if tile_x < 0.0 || tile_y < 0.0 || tile_x >= width as f64 || tile_y >= height as f64 {
+                        return Err(TiledError::ObjectOutOfBounds {
+                            name: kind,
+                            x: object.x,
+                            y: object.y,
+                        });
+                    }
+
+                    let tile = (tile_x as usize, tile_y as usize);
+                    if kind == "spawn" {
+                        if spawn.is_some() {
+                            return Err(TiledError::DuplicateSpawn);
+                        }
+                        spawn = Some(tile);
+                    } else {
+                        treasures.push(tile);
+                    }
+                }
+            }
+            "imagelayer" => {}
+            other => return Err(TiledError::Unsupported(format!("{} layers", other))),
+        }
+    }
+
+    let spawn = spawn.ok_or(TiledError::MissingSpawn)?;
+    if treasures.is_empty() {
+        return Err(TiledError::NoTreasures);
+    }
+
+    // 出生点和宝藏所在的格子必须可以行走
+    tiles[spawn.1 * width + spawn.0] = TileType::Empty;
+    for &(x, y) in &treasures {
+        tiles[y * width + x] = TileType::Empty;
+    }
+
+    // 与文本关卡一样，出生点和宝藏不能放在最外圈
+    let level = Level {
+        map: GameMap::from_tiles(width, height, tile_size, tiles),
+        spawn,
+        treasures,
+    };
+    level.validate().map_err(TiledError::InvalidLevel)?;
+    Ok(level)
+}
+
+fn tile_type_for(tilesets: &[TiledTileset], gid: u32, x: usize, y: usize) -> Result<TileType, TiledError> {
+    // 找到 firstgid 不大于 gid 的最后一个图块集
+    let tileset = tilesets.iter()
+        .filter(|tileset| tileset.firstgid <= gid)
+        .max_by_key(|tileset| tileset.firstgid)
+        .ok_or(TiledError::UnknownTileId { gid, x, y })?;
+    let local_id = gid - tileset.firstgid;
+    if tileset.tilecount > 0 && local_id >= tileset.tilecount {
+        return Err(TiledError::UnknownTileId { gid, x, y });
+    }
+
+    let tile = tileset.tiles.iter()
+        .find(|tile| tile.id == local_id)
+        .ok_or(TiledError::UnknownTileId { gid, x, y })?;
+
+    let value = tile.properties.iter()
+        .find(|property| property.name == "tile")
+        .and_then(|property| property.value.as_str())
+        .unwrap_or(&tile.class)
+        .to_lowercase();
+
+    match value.as_str() {
+        "wall" => Ok(TileType::Wall),
+        "obstacle" => Ok(TileType::Obstacle),
+        "empty" | "floor" => Ok(TileType::Empty),
+        _ => Err(TiledError::UnmappedTile { gid, value }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    // 5x3 的地图：外圈是墙，出生点在 (1, 1)，宝藏在 (3, 1)
+    fn map() -> Value {
+        json!({
+            "width": 5,
+            "height": 3,
+            "tilewidth": 32,
+            "tileheight": 32,
+            "orientation": "orthogonal",
+            "tilesets": [{
+                "firstgid": 1,
+                "tilecount": 2,
+                "tiles": [
+                    { "id": 0, "class": "wall" },
+                    { "id": 1, "properties": [{ "name": "tile", "value": "empty" }] }
+                ]
+            }],
+            "layers": [
+                {
+                    "type": "tilelayer",
+                    "name": "ground",
+                    "data": [1, 1, 1, 1, 1,
+                             1, 2, 0, 2, 1,
+                             1, 1, 1, 1, 1]
+                },
+                {
+                    "type": "objectgroup",
+                    "name": "objects",
+                    "objects": [
+                        { "class": "spawn", "x": 32, "y": 32, "width": 32, "height": 32 },
+                        { "name": "treasure", "x": 96, "y": 32, "width": 32, "height": 32 }
+                    ]
+                }
+            ]
+        })
+    }
+
+    fn import(map: &Value) -> Result<Level, TiledError> {
+        import_tiled_json(&map.to_string(), 40.0)
+    }
+
+    #[test]
+    fn imports_tiles_spawn_and_treasures() {
+        let level = import(&map()).unwrap();
+        assert_eq!(level.to_level_string(), "#####\n#P.T#\n#####\n");
+        assert_eq!(level.map.tile_size(), 40.0);
+    }
+
+    #[test]
+    fn rejects_infinite_maps() {
+        let mut map = map();
+        map["infinite"] = json!(true);
+        assert_eq!(import(&map).err(), Some(TiledError::Unsupported("infinite maps".to_string())));
+    }
+
+    #[test]
+    fn rejects_external_tilesets() {
+        let mut map = map();
+        map["tilesets"][0]["source"] = json!("tiles.tsj");
+        assert_eq!(import(&map).err(), Some(TiledError::Unsupported("external tilesets".to_string())));
+    }
+
+    #[test]
+    fn rejects_unknown_gids() {
+        let mut map = map();
+        map["layers"][0]["data"][7] = json!(9);
+        assert_eq!(import(&map).err(), Some(TiledError::UnknownTileId { gid: 9, x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn rejects_layer_size_mismatch() {
+        let mut map = map();
+        map["layers"][0]["data"].as_array_mut().unwrap().pop();
+        assert_eq!(import(&map).err(), Some(TiledError::LayerSizeMismatch { layer: "ground".to_string() }));
+    }
+
+    #[test]
+    fn rejects_objects_outside_the_map() {
+        let mut map = map();
+        map["layers"][1]["objects"][1]["x"] = json!(400);
+        assert_eq!(
+            import(&map).err(),
+            Some(TiledError::ObjectOutOfBounds { name: "treasure".to_string(), x: 400.0, y: 32.0 })
+        );
+    }
+
+    #[test]
+    fn rejects_huge_maps_before_allocating() {
+        for (width, height) in [(4_000_000_000usize, 4_000_000_000usize), (100_000, 100_000), (257, 3)] {
+            let mut map = map();
+            map["width"] = json!(width);
+            map["height"] = json!(height);
+            assert_eq!(import(&map).err(), Some(TiledError::MapTooLarge { width, height }));
+        }
+    }
+}
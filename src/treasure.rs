@@ -1,30 +1,157 @@
 // src/treasure.rs
-use wasm_bindgen::prelude::*;
-use js_sys::Math;
+use serde::{Deserialize, Serialize};
 
+use crate::combo::Combo;
 use crate::map::GameMap;
+use crate::rng::Rng;
 
-#[wasm_bindgen]
-#[derive(Clone, Copy)]
+// 地图有宝藏房间时，每次尝试放在宝藏房间里的概率
+const TREASURE_ROOM_BIAS: f64 = 0.75;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum TreasureKind {
+    #[default]
+    Coin,
+    Gem,
+    Chest,
+    // 沙漏：不加分，但延长时间限制
+    Hourglass,
+    // 被诅咒的宝藏：扣分，不需要收集也能过关
+    Cursed,
+    // 指南针：不加分，收集后一段时间内显示指向最近宝藏的箭头，不需要收集也能过关
+    Compass,
+}
+
+impl TreasureKind {
+    pub const ALL: [TreasureKind; 6] = [
+        TreasureKind::Coin,
+        TreasureKind::Gem,
+        TreasureKind::Chest,
+        TreasureKind::Hourglass,
+        TreasureKind::Cursed,
+        TreasureKind::Compass,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TreasureKind::Coin => "coin",
+            TreasureKind::Gem => "gem",
+            TreasureKind::Chest => "chest",
+            TreasureKind::Hourglass => "hourglass",
+            TreasureKind::Cursed => "cursed",
+            TreasureKind::Compass => "compass",
+        }
+    }
+
+    // 分值，以每个宝藏的基础分值（GameConfig::treasure_value）为单位
+    pub fn points(self, treasure_value: u32) -> i64 {
+        let value = treasure_value as i64;
+        match self {
+            TreasureKind::Coin => value,
+            TreasureKind::Gem => value * 3,
+            TreasureKind::Chest => value * 5,
+            TreasureKind::Hourglass | TreasureKind::Compass => 0,
+            TreasureKind::Cursed => -value * 2,
+        }
+    }
+
+    // 收集后增加的时间（秒）
+    pub fn time_bonus(self) -> f64 {
+        match self {
+            TreasureKind::Hourglass => 10.0,
+            _ => 0.0,
+        }
+    }
+
+    // 收集后显示方向箭头的时间（秒）
+    pub fn hint_duration(self) -> f64 {
+        match self {
+            TreasureKind::Compass => 15.0,
+            _ => 0.0,
+        }
+    }
+
+    // 直径（像素）
+    pub fn size(self) -> f64 {
+        match self {
+            TreasureKind::Coin | TreasureKind::Hourglass | TreasureKind::Cursed | TreasureKind::Compass => 20.0,
+            TreasureKind::Gem => 16.0,
+            TreasureKind::Chest => 28.0,
+        }
+    }
+
+    // 随机生成时的相对权重，越大越常见
+    pub fn weight(self) -> u32 {
+        match self {
+            TreasureKind::Coin => 60,
+            TreasureKind::Gem => 15,
+            TreasureKind::Chest => 5,
+            TreasureKind::Hourglass => 10,
+            TreasureKind::Cursed => 10,
+            TreasureKind::Compass => 8,
+        }
+    }
+
+    // 过关是否需要收集
+    pub fn is_required(self) -> bool {
+        !matches!(self, TreasureKind::Cursed | TreasureKind::Compass)
+    }
+
+    // 按权重随机选择一种
+    pub fn random(rng: &mut Rng) -> TreasureKind {
+        let total: u32 = TreasureKind::ALL.iter().map(|kind| kind.weight()).sum();
+        let mut roll = rng.range(0, total as usize) as u32;
+        for kind in TreasureKind::ALL {
+            if roll < kind.weight() {
+                return kind;
+            }
+            roll -= kind.weight();
+        }
+        TreasureKind::Coin
+    }
+}
+
+// 一次收集的结果
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Collected {
+    // 宝藏在 TreasureManager::treasures() 中的序号
+    pub index: usize,
+    pub kind: TreasureKind,
+    // 乘上连击倍率之后的得分
+    pub points: i64,
+    // 这是当前连击中的第几个，连击中断时为 0
+    pub chain: u32,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Treasure {
     x: f64,
     y: f64,
     size: f64,
     collected: bool,
+    #[serde(default)]
+    kind: TreasureKind,
 }
 
-#[wasm_bindgen]
 impl Treasure {
-    #[wasm_bindgen(constructor)]
     pub fn new(x: f64, y: f64) -> Treasure {
+        Treasure::with_kind(x, y, TreasureKind::Coin)
+    }
+
+    pub fn with_kind(x: f64, y: f64, kind: TreasureKind) -> Treasure {
         Treasure {
             x,
             y,
-            size: 20.0, // 宝藏大小
+            size: kind.size(),
             collected: false,
+            kind,
         }
     }
 
+    pub fn kind(&self) -> TreasureKind {
+        self.kind
+    }
+
     pub fn x(&self) -> f64 {
         self.x
     }
@@ -46,57 +173,106 @@ impl Treasure {
     }
 }
 
-#[wasm_bindgen]
 pub struct TreasureManager {
     treasures: Vec<Treasure>,
     score: u32,
+    // 每个宝藏的分值
+    treasure_value: u32,
+    combo: Combo,
+}
+
+impl Default for TreasureManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-#[wasm_bindgen]
 impl TreasureManager {
-    #[wasm_bindgen(constructor)]
     pub fn new() -> TreasureManager {
         TreasureManager {
             treasures: Vec::new(),
             score: 0,
+            treasure_value: 10,
+            combo: Combo::default(),
         }
     }
 
+    // 只在能从出生点走到的格子上放置宝藏，种类按权重随机，返回实际放置的宝藏数量
+    // 宝藏之间尽量相隔 min_spread 像素，尝试次数用掉一半后不再要求间距，避免地图太小时放不下
     pub fn generate_treasures(
-        &mut self, 
-        count: u32, 
-        map_width: f64, 
-        map_height: f64, 
-        tile_size: f64,
-        game_map: &GameMap,  // 添加地图参数用于碰撞检测
-    ) {
+        &mut self,
+        count: u32,
+        game_map: &GameMap,
+        spawn: (usize, usize),
+        min_spread: f64,
+        rng: &mut Rng,
+    ) -> u32 {
+        let tile_size = game_map.tile_size();
+        let map_width = game_map.width() as f64 * tile_size;
+        let map_height = game_map.height() as f64 * tile_size;
         self.treasures.clear();
+        self.combo.reset();
+        let reachable = game_map.reachable_from(spawn.0, spawn.1);
+        let treasure_rooms: Vec<_> = game_map.treasure_rooms().copied().collect();
         let mut placed_count = 0;
-        let max_attempts = count * 100; // 防止无限循环
+        let max_attempts = count.saturating_mul(100); // 防止无限循环
         let mut attempts = 0;
 
         while placed_count < count && attempts < max_attempts {
-            let x = Math::random() * (map_width - 2.0 * tile_size) + tile_size;
-            let y = Math::random() * (map_height - 2.0 * tile_size) + tile_size;
+            let (x, y) = if !treasure_rooms.is_empty() && rng.chance(TREASURE_ROOM_BIAS) {
+                // 在随机一个宝藏房间内取点
+                let room = treasure_rooms[rng.range(0, treasure_rooms.len())];
+                (
+                    (room.x as f64 + rng.next_f64() * room.width as f64) * tile_size,
+                    (room.y as f64 + rng.next_f64() * room.height as f64) * tile_size,
+                )
+            } else {
+                (
+                    rng.next_f64() * (map_width - 2.0 * tile_size) + tile_size,
+                    rng.next_f64() * (map_height - 2.0 * tile_size) + tile_size,
+                )
+            };
 
-            // 检查该位置是否可行走
-            if game_map.is_walkable(x, y) {
-                self.treasures.push(Treasure::new(x, y));
+            // 检查该位置是否可行走，并且能从出生点到达
+            let (tile_x, tile_y) = game_map.tile_at(x, y);
+            let spread_out = attempts * 2 >= max_attempts
+                || self.treasures.iter().all(|t| (t.x - x).hypot(t.y - y) >= min_spread);
+            if game_map.is_walkable(x, y) && reachable[tile_y * game_map.width() + tile_x] && spread_out {
+                self.treasures.push(Treasure::with_kind(x, y, TreasureKind::random(rng)));
                 placed_count += 1;
             }
 
             attempts += 1;
         }
 
-        // 如果无法放置所有宝藏，输出警告
-        if placed_count < count {
-            web_sys::console::log_1(&"Warning: Could not place all treasures in valid positions".into());
+        // 至少要有一个需要收集的宝藏，否则一开局就过关了
+        if !self.treasures.iter().any(|t| t.kind.is_required()) {
+            if let Some(first) = self.treasures.first_mut() {
+                *first = Treasure::new(first.x, first.y);
+            }
         }
+
+        placed_count
+    }
+
+    // 在给定的像素坐标放置宝藏（用于手工编辑的关卡）
+    pub fn place_treasures(&mut self, positions: &[(f64, f64)]) {
+        self.treasures = positions.iter().map(|&(x, y)| Treasure::new(x, y)).collect();
+        self.combo.reset();
     }
 
-    pub fn check_collection(&mut self, player_x: f64, player_y: f64, player_size: f64) -> bool {
-        let mut collected = false;
-        for treasure in &mut self.treasures {
+    // 从存档恢复宝藏、分数和连击进度
+    pub fn restore(&mut self, treasures: Vec<Treasure>, score: u32, combo: &Combo) {
+        self.treasures = treasures;
+        self.score = score;
+        self.combo.restore(combo);
+    }
+
+    // 收集玩家碰到的宝藏并计分（分数不会低于 0），返回这次收集的结果
+    // 加分的宝藏按连击倍率计分，扣分的宝藏中断连击
+    pub fn check_collection(&mut self, player_x: f64, player_y: f64, player_size: f64) -> Vec<Collected> {
+        let mut collected = Vec::new();
+        for (index, treasure) in self.treasures.iter_mut().enumerate() {
             if !treasure.is_collected() {
                 let dx = treasure.x - (player_x + player_size / 2.0);
                 let dy = treasure.y - (player_y + player_size / 2.0);
@@ -104,22 +280,34 @@ impl TreasureManager {
                 
                 if distance < (player_size + treasure.size) / 2.0 {
                     treasure.collect();
-                    self.score += 10;
-                    collected = true;
+                    let base = treasure.kind.points(self.treasure_value);
+                    let points = if base < 0 {
+                        self.combo.reset();
+                        base
+                    } else {
+                        (base as f64 * self.combo.hit()).round() as i64
+                    };
+                    self.score = (self.score as i64 + points).clamp(0, u32::MAX as i64) as u32;
+                    collected.push(Collected { index, kind: treasure.kind, points, chain: self.combo.chain() });
                 }
             }
         }
         collected
     }
 
+    // 所有需要收集的宝藏（被诅咒的和指南针除外）都已收集
     pub fn all_treasures_collected(&self) -> bool {
-        self.treasures.iter().all(|t| t.is_collected())
+        self.treasures.iter().all(|t| t.is_collected() || !t.kind.is_required())
     }
 
     pub fn get_score(&self) -> u32 {
         self.score
     }
 
+    pub fn treasures(&self) -> &[Treasure] {
+        &self.treasures
+    }
+
     pub fn get_treasure_count(&self) -> usize {
         self.treasures.len()
     }
@@ -132,6 +320,14 @@ impl TreasureManager {
         self.treasures.get(index).map(|t| t.y)
     }
 
+    pub fn get_treasure_kind(&self, index: usize) -> Option<TreasureKind> {
+        self.treasures.get(index).map(|t| t.kind)
+    }
+
+    pub fn get_treasure_size(&self, index: usize) -> Option<f64> {
+        self.treasures.get(index).map(|t| t.size)
+    }
+
     pub fn is_treasure_collected(&self, index: usize) -> bool {
         self.treasures.get(index).map(|t| t.is_collected()).unwrap_or(true)
     }
@@ -139,4 +335,25 @@ impl TreasureManager {
     pub fn reset_score(&mut self) {
         self.score = 0;
     }
+
+    pub fn set_score(&mut self, score: u32) {
+        self.score = score;
+    }
+
+    pub fn set_treasure_value(&mut self, treasure_value: u32) {
+        self.treasure_value = treasure_value;
+    }
+
+    pub fn set_combo_settings(&mut self, window: f64, decay: f64) {
+        self.combo.set_settings(window, decay);
+    }
+
+    // 连击计时，每个模拟步调用一次
+    pub fn update_combo(&mut self, delta_time: f64) {
+        self.combo.update(delta_time);
+    }
+
+    pub fn combo(&self) -> &Combo {
+        &self.combo
+    }
 }
\ No newline at end of file
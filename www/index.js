@@ -1,4 +1,4 @@
-import init, { Game, Renderer } from '../pkg/treasure_hunt_wasm.js';
+import init, { Game, GameState, Renderer } from '../pkg/treasure_hunt_wasm.js';
 
 let game = null;
 let renderer = null;
@@ -23,6 +23,39 @@ async function createGame(width, height) {
     }
 }
 
+// 根据下拉框选择地图生成方式
+function applyMapGenerator() {
+    const select = document.getElementById('map-select');
+    const value = select ? select.value : 'obstacles';
+    if (value === 'campaign') {
+        game.start_campaign();
+    } else if (value === 'braided') {
+        game.set_map_generator('backtracker', 0.5);
+    } else {
+        game.set_map_generator(value);
+    }
+}
+
+// 根据下拉框选择地图大小（格子数），比画布大的地图由摄像机跟随玩家滚动
+function applyMapSize() {
+    const select = document.getElementById('size-select');
+    const [width, height] = select && select.value !== 'fit'
+        ? select.value.split('x').map(Number)
+        : [0, 0];
+    const config = game.get_config();
+    if (config.map_width !== width || config.map_height !== height) {
+        config.map_width = width;
+        config.map_height = height;
+        game.set_config(config);
+    }
+}
+
+// 根据下拉框选择难度，必须在 start() 之前
+function applyDifficulty() {
+    const select = document.getElementById('difficulty-select');
+    game.set_difficulty(select ? select.value : 'normal');
+}
+
 async function startGame() {
     try {
         if (!game) {
@@ -39,13 +72,30 @@ async function startGame() {
 
             renderer = new Renderer(canvas, width, height);
             initializeEvents();
-        } else {
+        }
+
+        // 战役中过关后直接进入下一关，保留分数
+        const nextLevel = game.get_state() === GameState.Results && game.has_next_level();
+        if (!nextLevel) {
+            applyMapSize();
+            applyMapGenerator();
             game.reset();
+            applyDifficulty();
+        }
+
+        const nameInput = document.getElementById('player-name');
+        if (nameInput) {
+            game.set_player_name(nameInput.value);
+        }
+
+        const pauseButton = document.getElementById('pause-button');
+        if (pauseButton) {
+            pauseButton.textContent = 'Pause';
         }
 
         if (game && typeof game.start === 'function') {
             game.start();
-            console.log("Game started");
+            console.log("Game started, seed:", game.seed());
             gameLoop();
         } else {
             throw new Error("Game instance not properly initialized");
@@ -64,10 +114,59 @@ function stopGame() {
             cancelAnimationFrame(animationId);
             animationId = null;
         }
+        // 回到标题菜单
+        renderer.render(game);
+    }
+}
+
+// 暂停/继续切换
+function togglePause() {
+    if (!game) {
+        return;
+    }
+
+    const pauseButton = document.getElementById('pause-button');
+    if (game.is_paused()) {
+        game.resume();
+        if (pauseButton) {
+            pauseButton.textContent = 'Pause';
+        }
+        gameLoop();
+    } else {
+        // 只有游戏中才能暂停，开场倒计时和菜单中忽略
+        game.pause();
+        if (!game.is_paused()) {
+            return;
+        }
+        if (animationId) {
+            cancelAnimationFrame(animationId);
+            animationId = null;
+        }
+        renderer.render(game);
+        if (pauseButton) {
+            pauseButton.textContent = 'Resume';
+        }
     }
 }
 
 function handleKeyDown(event) {
+    // 输入名字时不处理游戏按键
+    if (event.target instanceof HTMLInputElement) {
+        return;
+    }
+    if (event.key === 'Escape' || event.key === 'p') {
+        togglePause();
+        return;
+    }
+    // + / - 缩放
+    if (game && (event.key === '+' || event.key === '=' || event.key === '-')) {
+        const factor = event.key === '-' ? 1 / 1.25 : 1.25;
+        game.set_camera_zoom(game.get_camera_zoom() * factor);
+        if (!game.is_running()) {
+            renderer.render(game);
+        }
+        return;
+    }
     if (game && game.is_running()) {
         game.handle_key_down(event.key);
     }
@@ -99,6 +198,7 @@ async function initialize() {
         const startButton = document.getElementById('start-button');
         const stopButton = document.getElementById('stop-button');
         const resetButton = document.getElementById('reset-button');
+        const pauseButton = document.getElementById('pause-button');
 
         if (startButton) {
             startButton.onclick = startGame;
@@ -109,6 +209,9 @@ async function initialize() {
         if (resetButton) {
             resetButton.onclick = startGame; // 重用 startGame 作为重置功能
         }
+        if (pauseButton) {
+            pauseButton.onclick = togglePause;
+        }
 
         console.log("Buttons initialized");
     } catch (error) {